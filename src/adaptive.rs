@@ -0,0 +1,343 @@
+//! Choosing which input size to measure next, so that an inference is reached
+//! in as few measurements as the data allows.
+//!
+//! A fixed ladder has to be sized for the worst case: wide enough and dense
+//! enough for models that are hard to tell apart, which on data where they are
+//! easy wastes most of its rungs — and the widest rungs are the expensive ones.
+//! Measuring one size at a time and asking after each what is still in doubt
+//! spends the measurements only where they change the answer.
+
+use crate::analysis::{Analysis, Inference};
+use crate::error::Error;
+use crate::fit::Fit;
+use crate::warning::Warning;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Confidence at which the driver stops by default.
+const TARGET: f64 = 0.9;
+
+/// Smallest input size measured by default.
+const SMALLEST: usize = 64;
+
+/// Largest input size the driver may reach by default.
+///
+/// Only an upper bound: the range grows by doubling and only while it has to,
+/// so most routines stop long before this.
+const LARGEST: usize = 1 << 24;
+
+/// Measures a routine one input size at a time, choosing each size by what the
+/// measurements so far leave undecided, until the inference is firm or the time
+/// budget is spent.
+///
+/// The driver starts from three sizes at the bottom of the range. After each
+/// measurement it infers, and stops once [`Inference::confidence`] reaches the
+/// target with neither [`Warning::TooFewPoints`] nor [`Warning::NarrowRange`]
+/// raised. Otherwise it measures next wherever the two best models disagree
+/// most: between two sizes already measured, or past the largest one — which
+/// is where a range too narrow to separate the models always disagrees most.
+///
+/// # Example
+/// ```
+/// use big_o::{Adaptive, Model};
+/// use std::time::Duration;
+///
+/// // Stands in for a benchmark: the cost of a routine at input size `n`.
+/// let measure = |n: usize| (n * n) as f64 + 100.0;
+///
+/// let inference = Adaptive::new(Duration::from_secs(10))
+///     .range(16, 1 << 20)
+///     .infer(measure)
+///     .unwrap();
+///
+/// assert_eq!(inference.best.model, Model::Quadratic);
+/// assert!(inference.confidence >= 0.9);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Adaptive {
+    budget: Duration,
+    target: f64,
+    smallest: usize,
+    largest: usize,
+    analysis: Analysis,
+}
+
+impl Adaptive {
+    /// A driver that may spend `budget` measuring, and stops sooner once the
+    /// inference reaches 90% confidence without a range warning.
+    ///
+    /// The budget is checked between measurements, so the last one may run
+    /// past it.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            target: TARGET,
+            smallest: SMALLEST,
+            largest: LARGEST,
+            analysis: Analysis::new(),
+        }
+    }
+
+    /// Stops once [`Inference::confidence`] reaches `confidence`.
+    pub fn target(mut self, confidence: f64) -> Self {
+        self.target = confidence;
+        self
+    }
+
+    /// Measures input sizes from `smallest` up to at most `largest`.
+    pub fn range(mut self, smallest: usize, largest: usize) -> Self {
+        self.smallest = smallest.min(largest).max(1);
+        self.largest = largest.max(smallest).max(1);
+        self
+    }
+
+    /// Infers with this analysis rather than the default one.
+    ///
+    /// Its accepted range is the one the driver stops at: a ladder declared
+    /// acceptable with [`Analysis::accept_range`] raises no range warning, so
+    /// the driver does not keep measuring to silence one.
+    pub fn analysis(mut self, analysis: Analysis) -> Self {
+        self.analysis = analysis;
+        self
+    }
+
+    /// Measures with `measure`, which returns the cost of the routine at the
+    /// input size it is given, and infers the complexity of what it measured.
+    ///
+    /// Returns the last inference made, firm or not, when the budget runs out
+    /// or every size worth measuring within the range has been measured.
+    ///
+    /// # Errors
+    /// As [`Analysis::infer`], for the measurements taken when the driver
+    /// stopped: a range too small to hold three distinct sizes is
+    /// [`Error::NotEnoughData`].
+    pub fn infer(&self, mut measure: impl FnMut(usize) -> f64) -> Result<Inference, Error> {
+        let start = Instant::now();
+        let mut measured: BTreeMap<usize, f64> = BTreeMap::new();
+        for factor in [1, 2, 4] {
+            let size = self.smallest.saturating_mul(factor).min(self.largest);
+            measured.entry(size).or_insert_with(|| measure(size));
+        }
+
+        loop {
+            let data: Vec<(f64, f64)> = measured.iter().map(|(&x, &y)| (x as f64, y)).collect();
+            let inference = self.analysis.infer(&data);
+            if inference.as_ref().is_ok_and(|i| self.is_firm(i)) || start.elapsed() >= self.budget {
+                return inference;
+            }
+            let best = inference.as_ref().ok().map(|i| (&i.best, runner_up(i)));
+            match self.next(&measured, best) {
+                Some(size) => {
+                    measured.insert(size, measure(size));
+                }
+                None => return inference,
+            }
+        }
+    }
+
+    /// Whether `inference` is as firm as the driver was asked to make it.
+    fn is_firm(&self, inference: &Inference) -> bool {
+        inference.confidence >= self.target
+            && !inference.warnings.iter().any(|warning| {
+                matches!(
+                    warning,
+                    Warning::TooFewPoints { .. } | Warning::NarrowRange { .. }
+                )
+            })
+    }
+
+    /// The size to measure next, or `None` if no size within the range is left
+    /// that would separate anything.
+    ///
+    /// The candidates are the geometric midpoint of every gap between measured
+    /// sizes, and double the largest one while the range allows it. Each is
+    /// scored by how far apart the two leading fits predict it to be, relative
+    /// to the best one's prediction: the size where they disagree most is the
+    /// one whose measurement would decide between them. Before there are two
+    /// fits to compare, the widest gap is taken.
+    fn next(
+        &self,
+        measured: &BTreeMap<usize, f64>,
+        fits: Option<(&Fit, Option<&Fit>)>,
+    ) -> Option<usize> {
+        let sizes: Vec<usize> = measured.keys().copied().collect();
+        // Each candidate with the width, in decades, of the gap it splits.
+        let mut candidates: Vec<(usize, f64)> = sizes
+            .windows(2)
+            .filter_map(|pair| {
+                let (below, above) = (pair[0] as f64, pair[1] as f64);
+                let middle = (below * above).sqrt().round() as usize;
+                (middle > pair[0] && middle < pair[1]).then(|| (middle, (above / below).log10()))
+            })
+            .collect();
+        if let Some(&largest) = sizes.last() {
+            let beyond = largest.saturating_mul(2).min(self.largest);
+            if beyond > largest {
+                candidates.push((beyond, (beyond as f64 / largest as f64).log10()));
+            }
+        }
+
+        let score = |size: usize, gap: f64| -> f64 {
+            let x = size as f64;
+            match fits {
+                Some((best, Some(other))) => {
+                    let expected = best.evaluate(x);
+                    let disagreement = (expected - other.evaluate(x)).abs() / expected.abs();
+                    match disagreement.is_finite() {
+                        true => disagreement,
+                        false => 0.0,
+                    }
+                }
+                _ => gap,
+            }
+        };
+
+        candidates
+            .into_iter()
+            .map(|(size, gap)| (size, score(size, gap)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(size, _)| size)
+    }
+}
+
+/// The best-ranked fit of a different model than the one chosen: the
+/// alternative the measurements have not yet ruled out.
+fn runner_up(inference: &Inference) -> Option<&Fit> {
+    inference
+        .all
+        .iter()
+        .find(|fit| fit.model != inference.best.model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::{fit_of, ModelParams};
+    use crate::model::Model;
+
+    #[test]
+    fn stops_as_soon_as_the_inference_is_firm() {
+        let mut sizes = Vec::new();
+
+        let inference = Adaptive::new(Duration::from_secs(60))
+            .range(10, 1 << 30)
+            .infer(|n| {
+                sizes.push(n);
+                (n as f64).powi(2)
+            })
+            .expect("quadratic data is inferable");
+
+        assert_eq!(inference.best.model, Model::Quadratic);
+        assert!(inference.confidence >= TARGET);
+        assert!(inference.warnings.is_empty(), "{:?}", inference.warnings);
+        assert!(
+            sizes.len() < 30,
+            "should not need many measurements, took {sizes:?}"
+        );
+        assert!(
+            *sizes.iter().max().unwrap() < 1 << 20,
+            "should not climb further than the warnings require"
+        );
+    }
+
+    #[test]
+    fn a_spent_budget_returns_what_was_measured() {
+        let mut calls = 0;
+
+        let inference = Adaptive::new(Duration::ZERO)
+            .range(10, 1000)
+            .infer(|n| {
+                calls += 1;
+                n as f64
+            })
+            .expect("three sizes are enough to infer from");
+
+        assert_eq!(calls, 3, "only the starting sizes");
+        assert!(inference
+            .warnings
+            .iter()
+            .any(|w| matches!(w, Warning::NarrowRange { .. })));
+    }
+
+    #[test]
+    fn stops_when_the_range_is_exhausted() {
+        let mut sizes = Vec::new();
+
+        let result = Adaptive::new(Duration::from_secs(60))
+            .range(1, 4)
+            .infer(|n| {
+                sizes.push(n);
+                n as f64
+            });
+
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 2, 3, 4], "every size in range, once each");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn a_range_without_three_sizes_is_not_enough_data() {
+        let err = Adaptive::new(Duration::from_secs(60))
+            .range(5, 6)
+            .infer(|n| n as f64)
+            .unwrap_err();
+
+        assert_eq!(err, Error::NotEnoughData { needed: 3, got: 2 });
+    }
+
+    #[test]
+    fn a_range_at_the_top_of_usize_does_not_overflow() {
+        let mut sizes = Vec::new();
+
+        let result = Adaptive::new(Duration::ZERO)
+            .range(usize::MAX / 3, usize::MAX)
+            .infer(|n| {
+                sizes.push(n);
+                n as f64
+            });
+
+        assert!(result.is_ok(), "{result:?}");
+        assert_eq!(sizes, vec![usize::MAX / 3, usize::MAX / 3 * 2, usize::MAX]);
+
+        let err = Adaptive::new(Duration::from_secs(60))
+            .range(usize::MAX - 1, usize::MAX)
+            .infer(|n| n as f64)
+            .unwrap_err();
+
+        // Both sizes round to the same `f64`, and count as one.
+        assert_eq!(err, Error::NotEnoughData { needed: 3, got: 1 });
+    }
+
+    #[test]
+    fn measures_where_the_leading_models_disagree() {
+        let driver = Adaptive::new(Duration::ZERO).range(1, 100);
+        let measured: BTreeMap<usize, f64> = [(1, 1.0), (10, 10.0), (100, 100.0)].into();
+        let uneven: BTreeMap<usize, f64> = [(1, 1.0), (10, 10.0), (1000, 1000.0)].into();
+        let fit = |model, params| fit_of(model, params, 0.0);
+        let linear = fit(
+            Model::Linear,
+            ModelParams::Linear {
+                gain: 1.0,
+                offset: 0.0,
+            },
+        );
+        let quadratic = fit(
+            Model::Quadratic,
+            ModelParams::Quadratic {
+                gain: 1.0,
+                offset: 0.0,
+            },
+        );
+
+        // The curves pull apart as the input grows, so the top gap decides
+        // between them and the bottom one barely does.
+        assert_eq!(
+            driver.next(&measured, Some((&linear, Some(&quadratic)))),
+            Some(32)
+        );
+        // With nothing to compare, the widest gap.
+        let driver = driver.range(1, 1000);
+        assert_eq!(driver.next(&uneven, Some((&linear, None))), Some(100));
+        assert_eq!(driver.next(&uneven, None), Some(100));
+    }
+}
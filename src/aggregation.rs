@@ -0,0 +1,167 @@
+//! How repeated measurements of one input size become the one cost fitted.
+//!
+//! The median is the default because it shrugs off a descheduled run without
+//! discarding the repetition. It is not the only defensible summary: the
+//! fastest of many timings is the best estimate of what the code itself costs,
+//! a throughput study wants the mean, and a latency budget is set by the tail.
+
+use crate::linalg;
+
+/// How [`Analysis`](crate::Analysis) collapses the measurements of one input
+/// size to a single cost.
+///
+/// Whatever the summary, the spread of the repeats is kept alongside it, so an
+/// input size measured fifty times is known to be known better than one
+/// measured twice.
+///
+/// # Example
+/// ```
+/// use big_o::{Aggregation, Analysis, Model};
+///
+/// // Ten timings per size, half of them slowed by other work on the machine.
+/// let data: Vec<(f64, f64)> = [100., 1_000., 10_000., 100_000.]
+///     .iter()
+///     .flat_map(|&n| (0..10).map(move |i| (n, 2.0 * n * (1.0 + (i % 2) as f64))))
+///     .collect();
+///
+/// let median = Analysis::new().infer(&data).unwrap();
+/// let fastest = Analysis::new()
+///     .aggregation(Aggregation::Min)
+///     .infer(&data)
+///     .unwrap();
+///
+/// assert_eq!(fastest.best.model, Model::Linear);
+/// assert!((median.best.evaluate(1_000.0) - 3_000.0).abs() < 1e-6);
+/// assert!((fastest.best.evaluate(1_000.0) - 2_000.0).abs() < 1e-6);
+/// ```
+///
+/// Non-exhaustive: a later release may add a summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Aggregation {
+    /// The middle measurement, or the mean of the middle two. Unmoved by any
+    /// minority of outliers, in either direction.
+    #[default]
+    Median,
+
+    /// The smallest measurement. Noise on a timing only ever adds — a cache
+    /// miss, a context switch — so the fastest run is the closest to the
+    /// code's own cost. Moved by a single run that was wrongly fast, such as
+    /// one the compiler optimized away.
+    Min,
+
+    /// The arithmetic mean, for costs that add up: throughput, total work. Any
+    /// single outlier moves it.
+    Mean,
+
+    /// The mean after dropping this fraction of the measurements from each
+    /// end, in `[0, 0.5)`: `0.1` drops the fastest and slowest tenth. Between
+    /// the mean, at zero, and the median as it approaches a half.
+    TrimmedMean(f64),
+
+    /// This quantile of the measurements, in `[0, 1]`, interpolated between
+    /// the nearest two: `0.99` is the p99 latency. `0.5` is the median.
+    Quantile(f64),
+}
+
+impl Aggregation {
+    /// Panics unless the summary's parameter, if it has one, is in range.
+    pub(crate) fn validate(self) {
+        match self {
+            Aggregation::TrimmedMean(fraction) => assert!(
+                (0.0..0.5).contains(&fraction),
+                "a trimmed mean drops a fraction in [0, 0.5) from each end, got {fraction}"
+            ),
+            Aggregation::Quantile(q) => assert!(
+                (0.0..=1.0).contains(&q),
+                "a quantile is a probability between 0 and 1, got {q}"
+            ),
+            Aggregation::Median | Aggregation::Min | Aggregation::Mean => {}
+        }
+    }
+
+    /// The variance of this summary of `n` measurements, as a multiple of
+    /// `variance / n`, under Gaussian noise and for large `n`.
+    ///
+    /// The median's is `π / 2`. The mean's is one, and so is taken to be the
+    /// others': a trimmed mean lies between the two, and the minimum and the
+    /// quantiles depend on the noise's tail. The factor scales every weight
+    /// alike, so it cancels from a fit, and only the reduced chi-squared is
+    /// out by it.
+    pub(crate) fn variance_factor(self) -> f64 {
+        match self {
+            Aggregation::Median => std::f64::consts::FRAC_PI_2,
+            _ => 1.0,
+        }
+    }
+
+    /// The summary of `values`, which it may reorder. `None` for none.
+    pub(crate) fn summarize(self, values: &mut [f64]) -> Option<f64> {
+        match self {
+            Aggregation::Median => linalg::median(values),
+            Aggregation::Min => values.iter().copied().reduce(f64::min),
+            Aggregation::Mean => linalg::mean(values.iter().copied()),
+            Aggregation::TrimmedMean(fraction) => {
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let trimmed = (values.len() as f64 * fraction) as usize;
+                linalg::mean(values[trimmed..values.len() - trimmed].iter().copied())
+            }
+            Aggregation::Quantile(q) => linalg::quantile(values, q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(aggregation: Aggregation) -> f64 {
+        let mut values = [12.0, 10.0, 11.0, 900.0, 13.0, 9.0, 14.0, 10.0, 11.0, 12.0];
+        aggregation.summarize(&mut values).unwrap()
+    }
+
+    #[test]
+    fn each_summary_reads_the_repeats_its_own_way() {
+        assert_eq!(summary(Aggregation::Median), 11.5);
+        assert_eq!(summary(Aggregation::Min), 9.0);
+        assert_eq!(summary(Aggregation::Mean), 100.2);
+        assert_eq!(summary(Aggregation::Quantile(0.0)), 9.0);
+        assert_eq!(summary(Aggregation::Quantile(1.0)), 900.0);
+    }
+
+    #[test]
+    fn a_trimmed_mean_drops_the_extremes_of_both_ends() {
+        // Dropping one from each end leaves 10, 10, 11, 11, 12, 12, 13, 14.
+        assert_eq!(summary(Aggregation::TrimmedMean(0.1)), 11.625);
+        assert_eq!(
+            summary(Aggregation::TrimmedMean(0.0)),
+            summary(Aggregation::Mean)
+        );
+    }
+
+    #[test]
+    fn a_single_measurement_is_its_own_summary() {
+        for aggregation in [
+            Aggregation::Median,
+            Aggregation::Min,
+            Aggregation::Mean,
+            Aggregation::TrimmedMean(0.4),
+            Aggregation::Quantile(0.99),
+        ] {
+            assert_eq!(aggregation.summarize(&mut [7.0]), Some(7.0));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "a trimmed mean drops a fraction in [0, 0.5)")]
+    fn trimming_half_from_each_end_leaves_nothing() {
+        Aggregation::TrimmedMean(0.5).validate();
+    }
+
+    #[test]
+    #[should_panic(expected = "a quantile is a probability between 0 and 1")]
+    fn a_quantile_is_a_probability() {
+        Aggregation::Quantile(99.0).validate();
+    }
+}
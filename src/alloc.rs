@@ -0,0 +1,234 @@
+//! Counting what a routine allocates, so its space complexity can be inferred
+//! the way its time complexity is.
+//!
+//! Time is the cost a benchmark reports, but memory that grows faster than it
+//! should is the one that pages someone at night: a cache with no bound, a
+//! buffer doubled once too often. Heap use can only be observed from inside the
+//! allocator, so this wraps one and counts.
+
+use crate::analysis::Inference;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A [`GlobalAlloc`] that forwards to another allocator and counts what passes
+/// through it: the bytes live at once, the bytes allocated in all, and how
+/// many allocations there were.
+///
+/// It counts only once installed as the program's global allocator, and then
+/// counts every thread: measure one routine at a time, and expect a few stray
+/// bytes from whatever else the program is doing. Pass it to
+/// [`Harness::infer_space`](crate::Harness::infer_space) to infer from the
+/// counts, or call [`measure`](Self::measure) directly.
+///
+/// # Example
+/// ```
+/// use big_o::CountingAllocator;
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+///
+/// fn main() {
+///     let (_, allocations) = ALLOCATOR.measure(|| vec![0u64; 1_000]);
+///
+///     assert!(allocations.peak_bytes >= 8_000);
+///     assert!(allocations.count >= 1);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+    live: AtomicUsize,
+    peak: AtomicUsize,
+    total: AtomicUsize,
+    count: AtomicUsize,
+}
+
+/// What a routine allocated while [`CountingAllocator::measure`] ran it.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Allocations {
+    /// The most bytes the routine held at once, above what was live when it
+    /// started: its space complexity, heap part.
+    pub peak_bytes: usize,
+
+    /// The bytes it allocated in all, whether or not it freed them before it
+    /// returned. A reallocation counts its new size.
+    pub total_bytes: usize,
+
+    /// How many times it allocated or reallocated.
+    pub count: usize,
+}
+
+/// The complexity of each thing [`Harness::infer_space`](crate::Harness::infer_space)
+/// counted, inferred from the same runs.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SpaceInference {
+    /// How the most heap held at once grows: the space complexity.
+    pub peak_bytes: Inference,
+
+    /// How the bytes allocated in all grow, freed or not. Above the peak's
+    /// growth, it is memory churned through: work for the allocator, and for
+    /// the cache.
+    pub total_bytes: Inference,
+
+    /// How the number of allocations grows. A routine that allocates once per
+    /// element where once per call would do shows here first.
+    pub allocations: Inference,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Counts what passes through `inner`. `const`, so it can initialize the
+    /// `static` a `#[global_allocator]` has to be.
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            live: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            total: AtomicUsize::new(0),
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Runs `routine` and reports what it allocated, along with what it
+    /// returned. Whatever `routine` frees before returning still counts towards
+    /// the peak and the total; what it returns is still live, and counts too.
+    pub fn measure<R>(&self, routine: impl FnOnce() -> R) -> (R, Allocations) {
+        let start = self.live.load(Ordering::SeqCst);
+        self.peak.store(start, Ordering::SeqCst);
+        let (total, count) = (
+            self.total.load(Ordering::SeqCst),
+            self.count.load(Ordering::SeqCst),
+        );
+
+        let result = routine();
+
+        let allocations = Allocations {
+            peak_bytes: self.peak.load(Ordering::SeqCst).saturating_sub(start),
+            total_bytes: self.total.load(Ordering::SeqCst).wrapping_sub(total),
+            count: self.count.load(Ordering::SeqCst).wrapping_sub(count),
+        };
+        (result, allocations)
+    }
+
+    /// Counts an allocation of `size` bytes that grew the live set by `grown`.
+    fn allocated(&self, size: usize, grown: usize) {
+        let live = self.live.fetch_add(grown, Ordering::SeqCst) + grown;
+        self.peak.fetch_max(live, Ordering::SeqCst);
+        self.total.fetch_add(size, Ordering::SeqCst);
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// SAFETY: every call is forwarded to `inner` unchanged, with the caller's own
+// guarantees; the counting beside it never touches the memory.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.allocated(layout.size(), layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.allocated(layout.size(), layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.live.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let moved = self.inner.realloc(ptr, layout, new_size);
+        if !moved.is_null() {
+            match new_size.checked_sub(layout.size()) {
+                Some(grown) => self.allocated(new_size, grown),
+                None => {
+                    self.live
+                        .fetch_sub(layout.size() - new_size, Ordering::SeqCst);
+                    self.total.fetch_add(new_size, Ordering::SeqCst);
+                    self.count.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Not installed: the calls are made by hand, so the counts are exact and
+    // no other test's allocations land in them.
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 8).unwrap()
+    }
+
+    #[test]
+    fn counts_the_peak_the_total_and_the_calls() {
+        let allocator = CountingAllocator::new(System);
+
+        let ((), allocations) = allocator.measure(|| unsafe {
+            let a = allocator.alloc(layout(100));
+            let b = allocator.alloc_zeroed(layout(50));
+            allocator.dealloc(a, layout(100));
+            let c = allocator.alloc(layout(30));
+            allocator.dealloc(b, layout(50));
+            allocator.dealloc(c, layout(30));
+        });
+
+        assert_eq!(
+            allocations,
+            Allocations {
+                peak_bytes: 150,
+                total_bytes: 180,
+                count: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn a_reallocation_moves_the_live_set_by_the_difference() {
+        let allocator = CountingAllocator::new(System);
+
+        let ((), allocations) = allocator.measure(|| unsafe {
+            let a = allocator.alloc(layout(64));
+            let a = allocator.realloc(a, layout(64), 256);
+            let a = allocator.realloc(a, layout(256), 16);
+            allocator.dealloc(a, layout(16));
+        });
+
+        assert_eq!(allocations.peak_bytes, 256);
+        assert_eq!(allocations.total_bytes, 64 + 256 + 16);
+        assert_eq!(allocations.count, 3);
+        assert_eq!(allocator.live.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn the_peak_is_above_what_was_live_before() {
+        let allocator = CountingAllocator::new(System);
+        let held = unsafe { allocator.alloc(layout(1_000)) };
+
+        let ((), allocations) = allocator.measure(|| unsafe {
+            let a = allocator.alloc(layout(10));
+            allocator.dealloc(a, layout(10));
+        });
+
+        assert_eq!(allocations.peak_bytes, 10);
+        unsafe { allocator.dealloc(held, layout(1_000)) };
+    }
+}
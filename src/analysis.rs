@@ -1,10 +1,19 @@
 //! Choosing between fitted models, and reporting how firm that choice is.
 
+use crate::aggregation::Aggregation;
 use crate::data::{self, Sample};
 use crate::error::Error;
-use crate::fit::{self, Fit};
+use crate::fit::{self, Fit, ModelParams};
+use crate::measurement::{self, Measurement};
 use crate::model::{self, Model};
+use crate::regime;
+use crate::robust::{self, Influence, Loss};
+use crate::selection::{self, Criterion, Selection};
+use crate::shape::Shape;
+use crate::uncertainty::{self, Uncertainty};
+use crate::validation::{self, Validation};
 use crate::warning::Warning;
+use std::fmt;
 
 /// How much worse than the best-scoring model a simpler one may score and still
 /// be preferred.
@@ -46,22 +55,22 @@ const PARAMETER_TOLERANCE: f64 = 0.15;
 const SCORE_FLOOR: f64 = 1e-8;
 
 /// Distinct input sizes below which the choice rests on very little.
-const ADVISED_POINTS: usize = 6;
+pub(crate) const ADVISED_POINTS: usize = 6;
 
 /// Decades of input size below which the models are hard to tell apart.
-const ADVISED_DECADES: f64 = 3.0;
+pub(crate) const ADVISED_DECADES: f64 = 3.0;
 
 /// Resamples drawn to estimate confidence.
 ///
 /// Enough to resolve the reported fraction to a percent or so, and cheap: the
 /// closed-form fit makes each resample a handful of passes over the sample.
-const RESAMPLES: usize = 100;
+pub(crate) const RESAMPLES: usize = 100;
 
 /// Fixed seed for the resampling.
 ///
 /// Inference is a pure function of its input: the same measurements must always
 /// produce the same confidence, or a CI job that asserts on it fails at random.
-const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+pub(crate) const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
 
 /// The outcome of inferring a complexity from measurements.
 ///
@@ -76,6 +85,15 @@ pub struct Inference {
     /// Every model that could be fitted, ordered best first.
     pub all: Vec<Fit>,
 
+    /// Each fit's information criterion and Akaike weight, in the order of
+    /// [`all`](Self::all).
+    ///
+    /// Under the criterion the analysis selected by, or AICc under the default
+    /// [`Selection::Tolerance`]. Empty in an inference stored before criteria
+    /// were reported.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub criteria: Vec<Criterion>,
+
     /// Fraction of resampled subsets of the measurements that chose the same
     /// model, in `[0, 1]`.
     ///
@@ -84,10 +102,131 @@ pub struct Inference {
     /// narrow a range of them.
     pub confidence: f64,
 
+    /// How often each model was chosen across the resamples behind
+    /// [`confidence`](Self::confidence), most often first.
+    ///
+    /// The runner-up here is the alternative the data keeps flirting with,
+    /// which is not always the runner-up in [`all`](Self::all). Models that no
+    /// resample chose are left out, and resamples too thin to choose from are
+    /// not counted, so the shares sum to one. Empty in an inference stored
+    /// before votes were reported.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub votes: Vec<Vote>,
+
+    /// The smallest and largest input size measured, which a
+    /// [prediction](Self::predict) or a [crossover](Self::crossover) outside
+    /// them extrapolates beyond. `None` in an inference stored before ranges
+    /// were reported.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub range: Option<(f64, f64)>,
+
+    /// The weight each measurement carried in [`best`](Self::best), in order
+    /// of input size, when the analysis fitted under a robust
+    /// [`loss`](Analysis::loss). A measurement near zero is one the fit set
+    /// aside as an outlier. Empty under least squares, where every weight is
+    /// one, and in an inference stored before influence was reported.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub influence: Vec<Influence>,
+
     /// Conditions that weaken the inference without invalidating it.
     pub warnings: Vec<Warning>,
 }
 
+/// Everything a reader needs to see why the inference came out as it did: the
+/// winning fit, every other fit that competed with it and how closely, the
+/// confidence, and the warnings. One item per line.
+impl fmt::Display for Inference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_ranking(f, &self.best, &self.all, self.confidence, |fit| {
+            Scores(fit).to_string()
+        })?;
+        let set_aside: Vec<String> = self
+            .influence
+            .iter()
+            .filter(|point| point.weight < robust::SET_ASIDE)
+            .map(|point| format!("n={} ({:.2})", point.size, point.weight))
+            .collect();
+        if !set_aside.is_empty() {
+            writeln!(f, "set aside:  {}", set_aside.join(", "))?;
+        }
+        write_warnings(f, &self.warnings)
+    }
+}
+
+/// Writes the winner, every other fit under `runners-up:`, and the confidence,
+/// a line each, with each fit followed by its `scores` in parentheses. Shared
+/// by every inference's `Display`, so that they read alike.
+pub(crate) fn write_ranking<T: PartialEq + fmt::Display>(
+    f: &mut fmt::Formatter,
+    best: &T,
+    all: &[T],
+    confidence: f64,
+    scores: impl Fn(&T) -> String,
+) -> fmt::Result {
+    writeln!(f, "best:       {best}  ({})", scores(best))?;
+    for (i, fit) in all.iter().filter(|fit| *fit != best).enumerate() {
+        let label = match i {
+            0 => "runners-up:",
+            _ => "",
+        };
+        writeln!(f, "{label:<11} {fit}  ({})", scores(fit))?;
+    }
+    writeln!(f, "confidence: {confidence:.2}")
+}
+
+/// Writes `warnings` under `warnings:`, one a line, or `none`. Ends without a
+/// newline, as the last item of every inference's `Display`.
+pub(crate) fn write_warnings(f: &mut fmt::Formatter, warnings: &[Warning]) -> fmt::Result {
+    match warnings {
+        [] => write!(f, "warnings:   none"),
+        warnings => {
+            for (i, warning) in warnings.iter().enumerate() {
+                match i {
+                    0 => write!(f, "warnings:   {warning}")?,
+                    _ => write!(f, "\n            {warning}")?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// One model's share of the resamples behind [`Inference::confidence`].
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Vote {
+    /// The model the resamples chose.
+    pub model: Model,
+
+    /// Fraction of the counted resamples that chose it, in `(0, 1]`.
+    pub share: f64,
+}
+
+/// A fit's scores as [`Inference`]'s `Display` lists them, with the held-out
+/// error where the fit was validated.
+struct Scores<'a>(&'a Fit);
+
+impl fmt::Display for Scores<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Scores(fit) = self;
+        write!(
+            f,
+            "relative error {:.4}, r^2 {:.4}",
+            fit.relative_error, fit.r_squared
+        )?;
+        if let Some(held_out) = fit.validation_error {
+            write!(f, ", held out {held_out:.4}")?;
+        }
+        match fit.reduced_chi_squared {
+            Some(chi_squared) => write!(f, ", chi^2/dof {chi_squared:.2}"),
+            None => Ok(()),
+        }
+    }
+}
+
 /// Inference with a restricted set of candidate models.
 ///
 /// # Example
@@ -109,6 +248,15 @@ pub struct Analysis {
     models: Vec<Model>,
     advised_points: usize,
     advised_decades: f64,
+    refine: bool,
+    aggregation: Aggregation,
+    outliers: Option<f64>,
+    loss: Loss,
+    selection: Selection,
+    validation: Option<Validation>,
+    resamples: usize,
+    seed: u64,
+    uncertainty: Option<f64>,
 }
 
 impl Default for Analysis {
@@ -118,12 +266,22 @@ impl Default for Analysis {
 }
 
 impl Analysis {
-    /// An analysis over every model the crate can fit.
+    /// An analysis over every model the crate can fit, short of the compound
+    /// ones that [`polylog`](Self::polylog) adds.
     pub fn new() -> Self {
         Self {
-            models: model::ALL.to_vec(),
+            models: model::DEFAULT.to_vec(),
             advised_points: ADVISED_POINTS,
             advised_decades: ADVISED_DECADES,
+            refine: false,
+            aggregation: Aggregation::Median,
+            outliers: None,
+            loss: Loss::Squared,
+            selection: Selection::Tolerance,
+            validation: None,
+            resamples: RESAMPLES,
+            seed: SEED,
+            uncertainty: None,
         }
     }
 
@@ -137,6 +295,230 @@ impl Analysis {
         self
     }
 
+    /// Adds the compound models — `O(log^2 n)`, `O(n log^2 n)` and
+    /// `O(n^2 log n)` — to the candidates.
+    ///
+    /// They are left out by default because each is a single logarithmic
+    /// factor away from a default model, and on noisy data with a large
+    /// constant term that factor is hard to see: adding them costs some
+    /// recovery of `O(n^2)` and `O(n log n)` themselves. Opt in where a polylog
+    /// factor is expected, rather than have it reported as a nearby `O(n^m)`.
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{Analysis, Model};
+    ///
+    /// let data: Vec<(f64, f64)> = (0..24)
+    ///     .map(|k| 10f64.powf(k as f64 / 6.0))
+    ///     .map(|n| (n, n * n.ln().powi(2) + 10.0))
+    ///     .collect();
+    ///
+    /// let inference = Analysis::new().polylog().infer(&data).unwrap();
+    ///
+    /// assert_eq!(inference.best.model, Model::LinearLogSquared);
+    /// assert_eq!(inference.best.to_string(), "O(n log^2 n)");
+    /// ```
+    pub fn polylog(mut self) -> Self {
+        let custom: Vec<Model> = self
+            .models
+            .iter()
+            .filter(|model| !model::ALL.contains(model))
+            .copied()
+            .collect();
+        self.models = model::ALL
+            .into_iter()
+            .filter(|model| self.models.contains(model) || model::POLYLOG.contains(model))
+            .chain(custom)
+            .collect();
+        self
+    }
+
+    /// Adds a caller's [`Shape`] to the candidates.
+    ///
+    /// It is fitted, scored, selected and resampled exactly as the built-in
+    /// models are, and appears in [`Inference::all`] as
+    /// [`Model::Custom`]. See [`Shape`] for an example.
+    pub fn shape(mut self, shape: &'static dyn Shape) -> Self {
+        let model = Model::Custom(shape);
+        if !self.models.contains(&model) {
+            self.models.push(model);
+        }
+        self
+    }
+
+    /// Refines the free-exponent and exponential fits in the original space,
+    /// with a constant term of their own.
+    ///
+    /// Both are fitted in closed form by taking the logarithm of the cost,
+    /// where `gain * n^power` is a straight line but `gain * n^power + offset`
+    /// is not. A fixed cost the benchmark pays at every size is then read as a
+    /// shallower exponent: a quadratic with a large setup cost comes out as
+    /// `O(n^1.7)`. Refinement starts from the closed-form fit and adjusts the
+    /// gain, the exponent or base, and an offset by Levenberg–Marquardt until
+    /// the relative error stops falling. A refined fit with an offset counts
+    /// the offset as one more free parameter when models are compared.
+    ///
+    /// Off by default: it is iterative where every other fit is closed-form,
+    /// and the named models already carry an offset.
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{Analysis, Model, ModelParams};
+    ///
+    /// let data: Vec<(f64, f64)> = (0..24)
+    ///     .map(|k| 10f64.powf(k as f64 / 6.0))
+    ///     .map(|n| (n, 2.0 * n.powf(1.5) + 5_000.0))
+    ///     .collect();
+    ///
+    /// let inference = Analysis::new().refine().infer(&data).unwrap();
+    ///
+    /// assert_eq!(inference.best.model, Model::Polynomial);
+    /// assert_eq!(inference.best.to_string(), "O(n^1.5)");
+    /// ```
+    pub fn refine(mut self) -> Self {
+        self.refine = true;
+        self
+    }
+
+    /// Collapses repeated measurements of an input size by `aggregation`
+    /// rather than to their median. See [`Aggregation`].
+    ///
+    /// # Panics
+    /// If a trimmed mean's fraction is outside `[0, 0.5)` or a quantile is
+    /// outside `[0, 1]`.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        aggregation.validate();
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Rejects outliers before fitting: every repeat more than `deviations`
+    /// from the median of its input size, and then every input size more than
+    /// `deviations` from a preliminary fit through the rest. What was rejected
+    /// is reported as [`Warning::OutliersRemoved`].
+    ///
+    /// A deviation is the median absolute deviation, scaled to the standard
+    /// deviation it implies under Gaussian noise; three is a common choice. The
+    /// preliminary fit is the analysis' own choice of model under the
+    /// [bisquare](Loss::Bisquare) loss, so the sizes it is judging do not bend
+    /// it. A size is only rejected if enough are left to fit.
+    ///
+    /// Unlike a robust [`loss`](Self::loss), which weighs a measurement down
+    /// and still reports it, this discards it: the same rule, applied the same
+    /// way by everyone who runs the analysis, in place of a filter by hand.
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{Analysis, Model, Warning};
+    ///
+    /// let mut data: Vec<(f64, f64)> = (0..8)
+    ///     .map(|k| 10f64.powf(2.0 + 3.0 * k as f64 / 7.0))
+    ///     .flat_map(|n| (0..5).map(move |i| (n, 3.0 * n * (1.0 + 0.01 * i as f64))))
+    ///     .collect();
+    /// // One run of the smallest size, and every run of the largest, were
+    /// // slowed by other work on the machine.
+    /// data[0].1 *= 40.0;
+    /// for run in &mut data[35..] {
+    ///     run.1 *= 3.0;
+    /// }
+    ///
+    /// let inference = Analysis::new().outliers(3.0).infer(&data).unwrap();
+    ///
+    /// assert_eq!(inference.best.model, Model::Linear);
+    /// assert!(inference.warnings.iter().any(|w| matches!(
+    ///     w,
+    ///     Warning::OutliersRemoved { count: 6, .. }
+    /// )));
+    /// ```
+    ///
+    /// # Panics
+    /// If `deviations` is not positive and finite.
+    pub fn outliers(mut self, deviations: f64) -> Self {
+        assert!(
+            deviations > 0.0 && deviations.is_finite(),
+            "outliers are measured in a positive, finite number of deviations, got {deviations}"
+        );
+        self.outliers = Some(deviations);
+        self
+    }
+
+    /// Fits every model under `loss` rather than least squares, so that a few
+    /// measurements far from the rest — a run descheduled on a shared machine
+    /// — are weighed down instead of steering the fit. See [`Loss`].
+    ///
+    /// The inference then reports each measurement's final weight as
+    /// [`Inference::influence`]. Held-out [`validation`](Self::validation)
+    /// fits each training split under `loss` too, but has no fit of a held-out
+    /// measurement's own to weigh it by, and counts it at full weight.
+    pub fn loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    /// Sets the rule that chooses the model to report. See [`Selection`].
+    ///
+    /// The rule decides the ranking of [`Inference::all`] and the resamples
+    /// behind [`Inference::confidence`] as well as the winner, so all three
+    /// describe the same choice.
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Measures every fit's out-of-sample error under `validation`, and reports
+    /// it as [`Fit::validation_error`].
+    ///
+    /// This only reports the error; to choose by it, select with
+    /// [`Selection::CrossValidation`], which validates by its own split.
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    /// Sets how many resamples [`Inference::confidence`] and
+    /// [`Inference::votes`] are estimated from. 100 by default.
+    ///
+    /// Each is a full refit of every model, so the cost of an inference grows
+    /// in proportion. The estimate's resolution does too: a hundred resolves
+    /// a share to a percent or so, ten thousand to about a tenth of that.
+    /// With the `rayon` feature the resamples are fitted in parallel. Zero
+    /// resamples skips the estimate, and reports a confidence of zero.
+    pub fn resamples(mut self, resamples: usize) -> Self {
+        self.resamples = resamples;
+        self
+    }
+
+    /// Sets the seed the resamples are drawn from.
+    ///
+    /// Inference is a pure function of the measurements and the seed, so the
+    /// same seed always gives the same confidence, in parallel or not. Varying
+    /// it shows how much of the confidence is the draw.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Estimates the uncertainty of every fitted coefficient, as intervals
+    /// that cover it with probability `level`, and reports it as
+    /// [`Fit::uncertainty`].
+    ///
+    /// Each fit's model is refitted to the same resamples that estimate
+    /// [`Inference::confidence`], and the intervals are the percentiles of
+    /// where its coefficients landed. So the number of resamples sets their
+    /// resolution, and the seed makes them reproducible. See [`Uncertainty`]
+    /// for an example.
+    ///
+    /// # Panics
+    /// If `level` is not strictly between zero and one.
+    pub fn uncertainty(mut self, level: f64) -> Self {
+        assert!(
+            level > 0.0 && level < 1.0,
+            "an interval's level is a probability strictly between 0 and 1, got {level}"
+        );
+        self.uncertainty = Some(level);
+        self
+    }
+
     /// Declares the ladder the caller can afford, so [`Warning::TooFewPoints`]
     /// and [`Warning::NarrowRange`] are raised only below it.
     ///
@@ -168,14 +550,33 @@ impl Analysis {
         self
     }
 
-    /// Infers the complexity of `data`.
+    /// Infers the complexity of `data`: any collection of [`Measurement`]s,
+    /// such as a slice of `(f64, f64)` or of `(usize, Duration)`.
+    ///
+    /// A measurement whose size or cost has no exact `f64` is fitted rounded
+    /// to the nearest, and counted in [`Warning::LossyConversion`].
     ///
     /// # Errors
     /// Returns [`Error::NotEnoughData`] if fewer than three distinct input
     /// sizes survive preparation, and [`Error::NoValidComplexity`] if none of
     /// the candidate models describes what does.
-    pub fn infer(&self, data: &[(f64, f64)]) -> Result<Inference, Error> {
-        let sample = data::prepare(data);
+    pub fn infer<M: Measurement>(
+        &self,
+        data: impl IntoIterator<Item = M>,
+    ) -> Result<Inference, Error> {
+        let (data, rounded) = measurement::collect(data);
+        let mut inference = self.infer_points(&data)?;
+        if rounded > 0 {
+            inference
+                .warnings
+                .push(Warning::LossyConversion { count: rounded });
+        }
+        Ok(inference)
+    }
+
+    /// [`infer`](Self::infer), from measurements already converted.
+    fn infer_points(&self, data: &[(f64, f64)]) -> Result<Inference, Error> {
+        let sample = self.prepare(data);
         let points = sample.points().len();
         if points < data::MIN_POINTS {
             return Err(Error::NotEnoughData {
@@ -184,80 +585,255 @@ impl Analysis {
             });
         }
 
-        let (all, unfittable) = self.fit_all(&sample);
-        let best = select(&all, points).ok_or(Error::NoValidComplexity)?;
+        let (mut all, unfittable) = self.fit_all(&sample);
+        if let Some(level) = self.uncertainty {
+            for fit in &mut all {
+                fit.uncertainty = self.uncertainty_of(&sample, fit, level);
+            }
+        }
+        let best = self.select(&all, points).ok_or(Error::NoValidComplexity)?;
+        let criteria = selection::weigh(
+            all.iter()
+                .map(|fit| (fit.model, self.criterion(fit, points))),
+        );
 
+        let (confidence, votes) = self.confidence(&sample, best.model);
         Ok(Inference {
-            confidence: self.confidence(&sample, best.model),
-            warnings: self.warnings(&sample, &unfittable),
+            confidence,
+            votes,
+            range: sample.range(),
+            influence: self.influence(&sample, &best),
+            warnings: self.warnings(&sample, &best, &unfittable),
             best,
             all,
+            criteria,
         })
     }
 
+    /// Prepares raw measurements for fitting, aggregating repeats and
+    /// rejecting outliers as the analysis does.
+    pub(crate) fn prepare(&self, data: &[(f64, f64)]) -> Sample {
+        let sample = data::prepare(data, self.aggregation, self.outliers);
+        match self.outliers {
+            Some(deviations) => self.without_outlying_sizes(sample, deviations),
+            None => sample,
+        }
+    }
+
+    /// `sample` without the input sizes more than `deviations` from a
+    /// preliminary fit.
+    ///
+    /// Deviations are of the relative residuals, scaled as the repeats were;
+    /// but never finer than a size's own standard error, so that a fit through
+    /// repeats that all agree does not reject a size for the noise left in
+    /// its median.
+    fn without_outlying_sizes(&self, sample: Sample, deviations: f64) -> Sample {
+        let points = sample.points().len();
+        let preliminary = Analysis {
+            loss: Loss::Bisquare,
+            ..self.in_sample()
+        };
+        let (fits, _) = preliminary.fit_all(&sample);
+        let Some(residuals) = preliminary
+            .select(&fits, points)
+            .and_then(|fit| fit::relative_errors(&fit.params, &sample))
+        else {
+            return sample;
+        };
+        let scale = robust::spread(&residuals);
+        let outlying: Vec<usize> = residuals
+            .iter()
+            .zip(sample.relative_standard_errors())
+            .enumerate()
+            .filter(|&(_, (residual, error))| residual.abs() > deviations * scale.max(error))
+            .map(|(i, _)| i)
+            .collect();
+        match points - outlying.len() >= data::MIN_POINTS {
+            true => sample.without(&outlying),
+            false => sample,
+        }
+    }
+
     /// Fits every candidate model to the sample.
     ///
     /// Returns the fits that can compete, best first, and the models that could
     /// not be fitted at all — as opposed to those that were fitted and then
     /// judged implausible, which had their say and lost.
-    fn fit_all(&self, sample: &Sample) -> (Vec<Fit>, Vec<Model>) {
+    pub(crate) fn fit_all(&self, sample: &Sample) -> (Vec<Fit>, Vec<Model>) {
         let points = sample.points().len();
         let mut fits: Vec<Fit> = Vec::with_capacity(self.models.len());
         let mut unfittable: Vec<Model> = Vec::new();
 
+        let validation = match self.selection {
+            Selection::CrossValidation(validation) => Some(validation),
+            _ => self.validation,
+        };
         for &model in &self.models {
-            match fit::fit(model, sample) {
+            let fitted = self.fit(model, sample).map(|fit| match validation {
+                Some(validation) => Fit {
+                    validation_error: validation::validation_error(
+                        sample,
+                        validation,
+                        |training| self.fit(model, training),
+                    ),
+                    ..fit
+                },
+                None => fit,
+            });
+            match fitted {
                 Some(fit) if is_plausible(&fit) => fits.push(fit),
                 Some(_) => {}
                 None => unfittable.push(model),
             }
         }
-        fits.sort_by(|a, b| cmp(corrected_error(a, points), corrected_error(b, points)));
+        match self.selection {
+            Selection::Tolerance => {
+                fits.sort_by(|a, b| cmp(corrected_error(a, points), corrected_error(b, points)))
+            }
+            // As below; a fit that could not be validated ranks last.
+            Selection::CrossValidation(_) => fits.sort_by(|a, b| {
+                let held_out = |fit: &Fit| {
+                    fit.validation_error
+                        .map_or(f64::INFINITY, |e| e.max(selection::SMALLEST_RELATIVE_ERROR))
+                };
+                cmp(held_out(a), held_out(b))
+                    .then(flexibility(a.model).cmp(&flexibility(b.model)))
+                    .then(tie_break(a, b, points))
+            }),
+            // Fits that tie on the criterion are exact fits held at its floor;
+            // among those, the less flexible one is the simpler explanation.
+            _ => fits.sort_by(|a, b| {
+                cmp(self.criterion(a, points), self.criterion(b, points))
+                    .then(flexibility(a.model).cmp(&flexibility(b.model)))
+                    .then(tie_break(a, b, points))
+            }),
+        }
         (fits, unfittable)
     }
 
+    /// This analysis without held-out validation, for a search that fits many
+    /// runs of the sample and would repeat every refit per fold for each one.
+    /// Cross-validated selection falls back to the criterion it weighs by.
+    pub(crate) fn in_sample(&self) -> Analysis {
+        let selection = match self.selection {
+            Selection::CrossValidation(_) => self.selection.criterion(),
+            other => other,
+        };
+        Analysis {
+            selection,
+            validation: None,
+            ..self.clone()
+        }
+    }
+
+    /// Fits `model` to `sample` under the analysis' loss, refined if the
+    /// analysis refines.
+    fn fit(&self, model: Model, sample: &Sample) -> Option<Fit> {
+        fit::fit(model, sample, self.loss).map(|fit| match self.refine {
+            true => fit::refine(fit, sample, self.loss),
+            false => fit,
+        })
+    }
+
+    /// The weight each measurement of `sample` carried in `best`, or nothing
+    /// under least squares.
+    fn influence(&self, sample: &Sample, best: &Fit) -> Vec<Influence> {
+        if self.loss == Loss::Squared {
+            return Vec::new();
+        }
+        let weights = fit::robust_weights(&best.params, sample, self.loss);
+        sample
+            .points()
+            .iter()
+            .zip(weights)
+            .map(|(&(size, cost), weight)| Influence { size, cost, weight })
+            .collect()
+    }
+
+    /// The value of the analysis' information criterion for `fit`.
+    fn criterion(&self, fit: &Fit, points: usize) -> f64 {
+        self.selection
+            .value(fit.relative_error, parameters(fit), points)
+    }
+
+    /// Picks the model to report from fits in [`fit_all`](Self::fit_all)'s
+    /// order. Every other rule has already ranked them; the tolerance rule
+    /// looks past the first.
+    pub(crate) fn select(&self, fitted: &[Fit], points: usize) -> Option<Fit> {
+        match self.selection {
+            Selection::Tolerance => select(fitted, points),
+            _ => fitted.first().copied(),
+        }
+    }
+
     /// Estimates how much the choice of model depends on which measurements
     /// happened to be taken.
     ///
     /// Refits repeated resamples of the measurements, drawn with replacement,
-    /// and reports the fraction that chose the same model. A choice driven by
-    /// the shape of the data survives having a few points swapped for
-    /// duplicates of others; a choice driven by one lucky measurement does not.
-    fn confidence(&self, sample: &Sample, best: Model) -> f64 {
-        let points = sample.points();
-        if points.is_empty() {
-            return 0.0;
+    /// and counts the models they chose. A choice driven by the shape of the
+    /// data survives having a few points swapped for duplicates of others; a
+    /// choice driven by one lucky measurement does not. Returns the fraction
+    /// that chose `best`, and the votes for every model chosen at all.
+    fn confidence(&self, sample: &Sample, best: Model) -> (f64, Vec<Vote>) {
+        if sample.points().is_empty() {
+            return (0.0, Vec::new());
         }
 
-        let mut rng = Rng::new(SEED);
-        let mut agreed = 0usize;
-        let mut compared = 0usize;
-        for _ in 0..RESAMPLES {
-            let drawn: Vec<(f64, f64)> = (0..points.len())
-                .filter_map(|_| points.get(rng.below(points.len())).copied())
-                .collect();
-            // Drawing with replacement duplicates input sizes, which collapse
-            // again on preparation; a draw left too thin proves nothing either
-            // way, so it is not counted rather than counted as disagreement.
-            let resample = data::prepare(&drawn);
-            if resample.points().len() < data::MIN_POINTS {
-                continue;
-            }
-            compared += 1;
+        let chosen = self.each_resample(|index| {
+            // A draw left too thin proves nothing either way, so it is not
+            // counted rather than counted as disagreement.
+            let resample = draw(sample, self.seed, index)?;
             let (refitted, _) = self.fit_all(&resample);
-            if select(&refitted, resample.points().len()).is_some_and(|fit| fit.model == best) {
-                agreed += 1;
+            self.select(&refitted, resample.points().len())
+                .map(|fit| fit.model)
+        });
+
+        let mut tally: Vec<(Model, usize)> = Vec::new();
+        for model in chosen.into_iter().flatten() {
+            match tally.iter_mut().find(|(voted, _)| *voted == model) {
+                Some((_, count)) => *count += 1,
+                None => tally.push((model, 1)),
             }
         }
-
-        match compared {
-            0 => 0.0,
-            _ => agreed as f64 / compared as f64,
+        let compared: usize = tally.iter().map(|&(_, count)| count).sum();
+        if compared == 0 {
+            return (0.0, Vec::new());
         }
+
+        // Ties keep the order the models were first chosen in, which does not
+        // depend on how the resamples were scheduled.
+        tally.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        let votes: Vec<Vote> = tally
+            .into_iter()
+            .map(|(model, count)| Vote {
+                model,
+                share: count as f64 / compared as f64,
+            })
+            .collect();
+        let confidence = votes
+            .iter()
+            .find(|vote| vote.model == best)
+            .map_or(0.0, |vote| vote.share);
+        (confidence, votes)
+    }
+
+    /// Runs `task` once per resample index.
+    fn each_resample<T: Send>(&self, task: impl Fn(usize) -> T + Send + Sync) -> Vec<T> {
+        each_resample(self.resamples, task)
+    }
+
+    /// The spread of `fit`'s coefficients when its model is refitted to each
+    /// resample of the measurements, at `level`.
+    fn uncertainty_of(&self, sample: &Sample, fit: &Fit, level: f64) -> Option<Uncertainty> {
+        let refits = self.each_resample(|index| {
+            let resample = draw(sample, self.seed, index)?;
+            self.fit(fit.model, &resample)
+        });
+        uncertainty::summarize(fit, level, &refits)
     }
 
     /// Collects everything about the sample that weakens the inference.
-    fn warnings(&self, sample: &Sample, unfittable: &[Model]) -> Vec<Warning> {
+    fn warnings(&self, sample: &Sample, best: &Fit, unfittable: &[Model]) -> Vec<Warning> {
         let mut warnings = Vec::new();
 
         let points = sample.points().len();
@@ -287,10 +863,55 @@ impl Analysis {
             warnings.push(Warning::ModelsSkipped(unfittable.to_vec()));
         }
 
+        let removed = sample.removed();
+        if !removed.is_empty() {
+            let mut sizes = removed.to_vec();
+            sizes.dedup();
+            warnings.push(Warning::OutliersRemoved {
+                count: removed.len(),
+                sizes,
+            });
+        }
+
+        let breakpoints = regime::breakpoints(self, sample, best);
+        if !breakpoints.is_empty() {
+            warnings.push(Warning::Piecewise { breakpoints });
+        }
+
         warnings
     }
 }
 
+/// Runs `task` once for each of `resamples` indices, in parallel with the
+/// `rayon` feature. The results are in index order either way.
+pub(crate) fn each_resample<T: Send>(
+    resamples: usize,
+    task: impl Fn(usize) -> T + Send + Sync,
+) -> Vec<T> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        (0..resamples).into_par_iter().map(task).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        (0..resamples).map(task).collect()
+    }
+}
+
+/// The `index`th resample of `sample`: as many points as it has, drawn with
+/// replacement from the `index`th stream of `seed`, so that the draw does not
+/// depend on the order resamples are taken in.
+///
+/// An input size drawn more than once is kept once. `None` if too few distinct
+/// ones are left to fit.
+fn draw(sample: &Sample, seed: u64, index: usize) -> Option<Sample> {
+    let len = sample.points().len();
+    let mut rng = Rng::stream(seed, index as u64);
+    let resample = sample.select((0..len).map(|_| rng.below(len)));
+    (resample.points().len() >= data::MIN_POINTS).then_some(resample)
+}
+
 /// How close a fitted base may come to one before the exponential it belongs to
 /// is really a constant wearing an exponential's name.
 const DEGENERATE_BASE: f64 = 1e-3;
@@ -309,20 +930,35 @@ fn is_plausible(fit: &Fit) -> bool {
     use crate::fit::ModelParams::*;
     match fit.params {
         Constant { offset } => offset >= 0.0,
-        Exponential { gain, base } => gain >= 0.0 && (base - 1.0).abs() > DEGENERATE_BASE,
+        Exponential { gain, base, .. } => gain >= 0.0 && (base - 1.0).abs() > DEGENERATE_BASE,
         Logarithmic { gain, .. }
+        | LogSquared { gain, .. }
+        | CubeRoot { gain, .. }
+        | SquareRoot { gain, .. }
         | Linear { gain, .. }
         | Linearithmic { gain, .. }
+        | LinearLogSquared { gain, .. }
         | Quadratic { gain, .. }
+        | QuadraticLog { gain, .. }
         | Cubic { gain, .. }
-        | Polynomial { gain, .. } => gain >= 0.0,
+        | Polynomial { gain, .. }
+        | Custom { gain, .. } => gain >= 0.0,
     }
 }
 
-/// Free parameters the model estimates from the data.
-fn parameters(model: Model) -> usize {
-    match model {
-        Model::Constant => 1,
+/// Free parameters the fit estimated from the data.
+///
+/// A [refined](Analysis::refine) fit that found a constant term spent one more
+/// than its closed-form seed did.
+pub(crate) fn parameters(fit: &Fit) -> usize {
+    match fit.params {
+        ModelParams::Constant { .. } => 1,
+        ModelParams::Custom { shape, .. } => shape.parameters(),
+        ModelParams::Polynomial { offset, .. } | ModelParams::Exponential { offset, .. }
+            if offset != 0.0 =>
+        {
+            3
+        }
         _ => 2,
     }
 }
@@ -335,10 +971,15 @@ fn parameters(model: Model) -> usize {
 /// correction for that, and without it a model with a spare parameter beats the
 /// right one on flat data every time.
 fn corrected_error(fit: &Fit, points: usize) -> f64 {
-    let spent = parameters(fit.model);
+    corrected(fit.relative_error, parameters(fit), points)
+}
+
+/// [`corrected_error`] for a relative error reached by spending `spent` free
+/// parameters on `points` points.
+pub(crate) fn corrected(relative_error: f64, spent: usize, points: usize) -> f64 {
     match points > spent {
-        true => fit.relative_error * (points as f64 / (points - spent) as f64).sqrt(),
-        false => fit.relative_error,
+        true => relative_error * (points as f64 / (points - spent) as f64).sqrt(),
+        false => relative_error,
     }
 }
 
@@ -363,10 +1004,26 @@ fn cmp(a: f64, b: f64) -> std::cmp::Ordering {
 /// Whether `candidate` describes the data as well as `best` does, once the
 /// freedom each of them had to bend towards the noise is allowed for.
 fn fits_as_well_as(candidate: &Fit, best: &Fit, points: usize) -> bool {
-    let saved = parameters(best.model).saturating_sub(parameters(candidate.model));
+    scores_as_well_as(
+        corrected_error(candidate, points),
+        parameters(candidate),
+        corrected_error(best, points),
+        parameters(best),
+    )
+}
+
+/// [`fits_as_well_as`] for two corrected errors and the free parameters each
+/// was reached with.
+pub(crate) fn scores_as_well_as(
+    candidate: f64,
+    candidate_parameters: usize,
+    best: f64,
+    best_parameters: usize,
+) -> bool {
+    let saved = best_parameters.saturating_sub(candidate_parameters);
     let allowance = 1.0 + SIMPLICITY_TOLERANCE + PARAMETER_TOLERANCE * saved as f64;
 
-    corrected_error(candidate, points) <= corrected_error(best, points) * allowance + SCORE_FLOOR
+    candidate <= best * allowance + SCORE_FLOOR
 }
 
 /// Picks the model to report from fits ordered best-scoring first.
@@ -388,21 +1045,41 @@ fn select(fitted: &[Fit], points: usize) -> Option<Fit> {
             // `O(n)` for data that is `O(n log n)` with a large constant term.
             flexibility(a.model)
                 .cmp(&flexibility(b.model))
-                .then(cmp(corrected_error(a, points), corrected_error(b, points)))
+                .then(tie_break(a, b, points))
         })
         .copied()
         .or(Some(best))
 }
 
+/// Orders two fits with the same freedom by their corrected error, or, where
+/// both are exact to within [`SCORE_FLOOR`], by degree, slower growth first.
+///
+/// Below the floor the errors are rounding noise, and ranking by them lets a
+/// change of units decide between two exact fits: `O(sqrt n)` and
+/// `O(n^(1/3))` through the same three points swapped places when the costs
+/// were rescaled. Neither is the better fit there, and the slower growth is
+/// the more cautious claim.
+fn tie_break(a: &Fit, b: &Fit, points: usize) -> std::cmp::Ordering {
+    let error = |fit: &Fit| corrected_error(fit, points).max(SCORE_FLOOR);
+    cmp(error(a), error(b)).then(cmp(a.degree(), b.degree()))
+}
+
 /// A xorshift64 generator.
 ///
 /// Seeded from a constant so that inference stays a pure function of its input;
 /// resampling needs values that are spread out, not values that are secret.
-struct Rng(u64);
+pub(crate) struct Rng(u64);
 
 impl Rng {
-    fn new(seed: u64) -> Self {
-        Self(seed)
+    /// The `index`th of the independent generators drawn from `seed`.
+    pub(crate) fn stream(seed: u64, index: u64) -> Self {
+        // The SplitMix64 finalizer, so that neighbouring indices and seeds
+        // start far apart rather than a few states along the same sequence.
+        let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        // Any non-zero state will do; xorshift is stuck at zero.
+        Self((z ^ (z >> 31)) | 1)
     }
 
     fn next_u64(&mut self) -> u64 {
@@ -415,7 +1092,7 @@ impl Rng {
     }
 
     /// A value in `0..n`, or `0` if `n` is zero.
-    fn below(&mut self, n: usize) -> usize {
+    pub(crate) fn below(&mut self, n: usize) -> usize {
         match n {
             0 => 0,
             n => (self.next_u64() % n as u64) as usize,
@@ -426,21 +1103,12 @@ impl Rng {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fit::ModelParams;
+    use crate::fit::{fit_of, ModelParams};
 
     /// A sample size typical of a real sweep, for the degrees-of-freedom
     /// correction the selection applies.
     const POINTS: usize = 24;
 
-    fn fit_of(model: Model, params: ModelParams, relative_error: f64) -> Fit {
-        Fit {
-            model,
-            params,
-            r_squared: 1.0 - relative_error,
-            relative_error,
-        }
-    }
-
     fn quadratic(relative_error: f64) -> Fit {
         fit_of(
             Model::Quadratic,
@@ -455,7 +1123,11 @@ mod tests {
     fn polynomial(power: f64, relative_error: f64) -> Fit {
         fit_of(
             Model::Polynomial,
-            ModelParams::Polynomial { gain: 1.0, power },
+            ModelParams::Polynomial {
+                gain: 1.0,
+                power,
+                offset: 0.0,
+            },
             relative_error,
         )
     }
@@ -550,6 +1222,7 @@ mod tests {
             ModelParams::Exponential {
                 gain: 5.0,
                 base: 1.0,
+                offset: 0.0,
             },
             0.0,
         );
@@ -558,6 +1231,7 @@ mod tests {
             ModelParams::Exponential {
                 gain: 5.0,
                 base: 1.5,
+                offset: 0.0,
             },
             0.0,
         );
@@ -568,14 +1242,29 @@ mod tests {
 
     #[test]
     fn resampling_is_reproducible() {
-        let mut a = Rng::new(SEED);
-        let mut b = Rng::new(SEED);
+        let mut a = Rng::stream(SEED, 0);
+        let mut b = Rng::stream(SEED, 0);
 
         let drawn: Vec<usize> = (0..32).map(|_| a.below(10)).collect();
 
         assert!(drawn.iter().all(|&i| i < 10));
         assert_eq!(drawn, (0..32).map(|_| b.below(10)).collect::<Vec<_>>());
         assert!(drawn.windows(2).any(|pair| pair[0] != pair[1]), "not stuck");
-        assert_eq!(Rng::new(SEED).below(0), 0);
+        assert_eq!(Rng::stream(SEED, 0).below(0), 0);
+    }
+
+    #[test]
+    fn streams_are_distinct_and_never_stuck() {
+        let first: Vec<usize> = {
+            let mut rng = Rng::stream(SEED, 0);
+            (0..16).map(|_| rng.below(1000)).collect()
+        };
+        let second: Vec<usize> = {
+            let mut rng = Rng::stream(SEED, 1);
+            (0..16).map(|_| rng.below(1000)).collect()
+        };
+
+        assert_ne!(first, second);
+        assert_ne!(Rng::stream(0, 0).next_u64(), 0);
     }
 }
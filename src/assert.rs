@@ -0,0 +1,253 @@
+//! Asserting on a complexity in a test, with a failure that explains itself.
+//!
+//! `assert!(inference.best.is_at_most(Model::Quadratic))` fails with the text
+//! of the condition and nothing else, so the first thing anyone does with a red
+//! run is reproduce it locally to find out what was inferred instead. Everything
+//! they would print is already in the [`Inference`]; the macro prints it.
+
+use crate::analysis::{Analysis, Inference};
+use crate::measurement::Measurement;
+use crate::model::Model;
+
+/// Asserts that measurements have a complexity, printing the whole inference
+/// if they do not.
+///
+/// Takes the measurements — a slice, array or `Vec` of any
+/// [`Measurement`](crate::Measurement), such as `(usize, Duration)` pairs, or a
+/// reference to one — and one of three bounds:
+///
+/// - `at_most = model`: the inferred complexity grows no faster than `model`,
+///   as [`Fit::is_at_most`](crate::Fit::is_at_most).
+/// - `faster_than = model`: it grows strictly slower, as
+///   [`Fit::is_faster_than`](crate::Fit::is_faster_than).
+/// - `exactly = model`: the chosen model is `model`.
+///
+/// Any of them may be followed by `min_confidence = c`, which also requires
+/// [`Inference::confidence`] of at least `c`.
+///
+/// On failure the panic message names the bound, the winning fit, every other
+/// fit that competed with its error, the confidence and every warning — what a
+/// failing CI run needs to be read rather than re-run.
+///
+/// # Panics
+/// When the bound does not hold, the confidence is too low, or no complexity
+/// can be inferred from the measurements at all.
+///
+/// # Example
+/// ```
+/// use big_o::{assert_complexity, Model};
+///
+/// let data: Vec<(f64, f64)> = (1..=40).map(|n| (n as f64, 3.0 * n as f64 + 2.0)).collect();
+///
+/// assert_complexity!(data, at_most = Model::Quadratic);
+/// assert_complexity!(data, exactly = Model::Linear, min_confidence = 0.8);
+/// ```
+///
+/// Timings can be asserted on as they were recorded:
+/// ```
+/// use big_o::{assert_complexity, Model};
+/// use std::time::Duration;
+///
+/// let timings: Vec<(usize, Duration)> = (1..=40)
+///     .map(|n| (n, Duration::from_micros(5 * n as u64 * n as u64)))
+///     .collect();
+///
+/// assert_complexity!(&timings, exactly = Model::Quadratic);
+/// ```
+#[macro_export]
+macro_rules! assert_complexity {
+    ($data:expr, at_most = $model:expr $(, min_confidence = $confidence:expr)? $(,)?) => {
+        $crate::__private::assert_complexity(
+            &$data,
+            $crate::__private::Bound::AtMost($model),
+            None $(.or(Some($confidence)))?,
+        )
+    };
+    ($data:expr, faster_than = $model:expr $(, min_confidence = $confidence:expr)? $(,)?) => {
+        $crate::__private::assert_complexity(
+            &$data,
+            $crate::__private::Bound::FasterThan($model),
+            None $(.or(Some($confidence)))?,
+        )
+    };
+    ($data:expr, exactly = $model:expr $(, min_confidence = $confidence:expr)? $(,)?) => {
+        $crate::__private::assert_complexity(
+            &$data,
+            $crate::__private::Bound::Exactly($model),
+            None $(.or(Some($confidence)))?,
+        )
+    };
+}
+
+/// What [`assert_complexity!`] asserts about the inferred complexity.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub enum Bound {
+    AtMost(Model),
+    FasterThan(Model),
+    Exactly(Model),
+}
+
+impl Bound {
+    fn holds(self, inference: &Inference) -> bool {
+        match self {
+            Bound::AtMost(model) => inference.best.is_at_most(model),
+            Bound::FasterThan(model) => inference.best.is_faster_than(model),
+            Bound::Exactly(model) => inference.best.model == model,
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Bound::AtMost(model) => format!("at most {model}"),
+            Bound::FasterThan(model) => format!("faster than {model}"),
+            Bound::Exactly(model) => format!("exactly {model}"),
+        }
+    }
+}
+
+/// What [`assert_complexity!`] takes measurements from: anything that holds
+/// them in a slice, seen through as many references as the caller wrote.
+#[doc(hidden)]
+pub trait Measurements {
+    type Item: Measurement;
+
+    fn as_slice(&self) -> &[Self::Item];
+}
+
+impl<M: Measurement> Measurements for [M] {
+    type Item = M;
+
+    fn as_slice(&self) -> &[M] {
+        self
+    }
+}
+
+impl<M: Measurement, const N: usize> Measurements for [M; N] {
+    type Item = M;
+
+    fn as_slice(&self) -> &[M] {
+        self
+    }
+}
+
+impl<M: Measurement> Measurements for Vec<M> {
+    type Item = M;
+
+    fn as_slice(&self) -> &[M] {
+        self
+    }
+}
+
+impl<T: Measurements + ?Sized> Measurements for &T {
+    type Item = T::Item;
+
+    fn as_slice(&self) -> &[T::Item] {
+        (**self).as_slice()
+    }
+}
+
+/// The body of [`assert_complexity!`], out of line so that a test which
+/// asserts in dozens of places compiles it once per measurement type.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_complexity<D: Measurements + ?Sized>(
+    data: &D,
+    bound: Bound,
+    min_confidence: Option<f64>,
+) {
+    let inference = match Analysis::new().infer(data.as_slice()) {
+        Ok(inference) => inference,
+        Err(e) => panic!(
+            "complexity assertion failed: expected {}, but nothing could be inferred: {e}",
+            bound.describe()
+        ),
+    };
+
+    let mut failures = Vec::new();
+    if !bound.holds(&inference) {
+        failures.push(format!(
+            "expected {}, inferred {}",
+            bound.describe(),
+            inference.best
+        ));
+    }
+    if let Some(required) = min_confidence {
+        if inference.confidence < required {
+            failures.push(format!(
+                "expected confidence of at least {required}, got {:.2}",
+                inference.confidence
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "complexity assertion failed: {}\n{}",
+            failures.join("; "),
+            report(&inference)
+        );
+    }
+}
+
+/// The inference as [`Inference`]'s `Display` writes it, indented to sit under
+/// the panic's first line.
+fn report(inference: &Inference) -> String {
+    inference
+        .to_string()
+        .lines()
+        .map(|line| format!("  {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quadratic() -> Vec<(f64, f64)> {
+        (1..=30).map(|n| (n as f64, (n * n) as f64)).collect()
+    }
+
+    #[test]
+    fn the_report_shows_every_competing_fit() {
+        let inference = Analysis::new().infer(quadratic()).unwrap();
+
+        let report = report(&inference);
+
+        assert!(report.contains("best:       O(n^2)"), "{report}");
+        assert!(report.contains("runners-up:"), "{report}");
+        assert_eq!(
+            report.matches("relative error").count(),
+            inference.all.len(),
+            "{report}"
+        );
+        assert!(report.contains("confidence: "), "{report}");
+        assert!(report.contains("input sizes span"), "{report}");
+    }
+
+    #[test]
+    fn a_bound_that_holds_is_silent() {
+        assert_complexity(&quadratic(), Bound::AtMost(Model::Cubic), Some(0.5));
+        assert_complexity(&quadratic(), Bound::Exactly(Model::Quadratic), None);
+        assert_complexity(&quadratic(), Bound::FasterThan(Model::Cubic), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected at most O(n), inferred O(n^2)")]
+    fn a_bound_that_fails_names_both_sides() {
+        assert_complexity(&quadratic(), Bound::AtMost(Model::Linear), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected confidence of at least 1.1")]
+    fn too_little_confidence_fails() {
+        assert_complexity(&quadratic(), Bound::Exactly(Model::Quadratic), Some(1.1));
+    }
+
+    #[test]
+    #[should_panic(expected = "nothing could be inferred: Need at least 3")]
+    fn nothing_to_infer_from_fails() {
+        assert_complexity(&[(1., 1.)], Bound::AtMost(Model::Cubic), None);
+    }
+}
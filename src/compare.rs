@@ -0,0 +1,446 @@
+//! Comparing a fresh inference against a stored one, so that a change in
+//! complexity can gate a merge without every change in noise doing the same.
+
+use crate::analysis::Inference;
+use crate::fit::{Fit, ModelParams};
+use crate::model::Model;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// How far a fitted exponent may move, in the exponent's own units, before the
+/// move is reported rather than put down to noise.
+///
+/// A few percent of timing noise moves a fitted exponent by a few hundredths
+/// between runs of the same code. A tenth is past that, and short of the
+/// smallest step between named models that matters: `O(n)` to `O(n log n)` is
+/// 0.13 on the degree scale, and is reported.
+///
+/// The base of `O(c^n)` is measured by its growth rate `ln c` instead, as a
+/// share of the baseline's: the cost at a given size is `c^n = e^(n ln c)`, so
+/// a tenth more growth rate is a tenth more of the exponent at every size,
+/// whereas the same step in `c` itself is a rounding error at `c = 10` and a
+/// ninefold rate at `c = 1.01`.
+const EXPONENT_TOLERANCE: f64 = 0.1;
+
+/// How far confidence may fall before the fall is reported.
+///
+/// Confidence is the share of [`Analysis::resamples`](crate::Analysis::resamples)
+/// that chose the winner, so at the default hundred it moves by a few
+/// hundredths between runs whose data differs only in noise, and by less with
+/// more resamples. A fifth is past that at any number of them.
+const CONFIDENCE_TOLERANCE: f64 = 0.2;
+
+/// How a fresh inference differs from a baseline.
+///
+/// One reason per verdict, the weightiest that applies: a change of complexity
+/// class outranks a drifted exponent, which outranks a fall in confidence.
+///
+/// Non-exhaustive: match with a `_` arm, or ask [`Verdict::is_regression`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Verdict {
+    /// The complexity is the baseline's, within tolerance.
+    Unchanged,
+
+    /// The cost now grows faster than it did: the regression a CI gate exists
+    /// to catch.
+    Grew {
+        /// What the baseline inferred.
+        baseline: Fit,
+        /// What the fresh run inferred.
+        current: Fit,
+    },
+
+    /// The cost now grows slower than it did.
+    Shrank {
+        /// What the baseline inferred.
+        baseline: Fit,
+        /// What the fresh run inferred.
+        current: Fit,
+    },
+
+    /// The same model with a free parameter — the exponent of `O(n^m)`, the
+    /// base of `O(c^n)` — fitted further from the baseline's than tolerance
+    /// allows.
+    Drifted {
+        /// What the baseline inferred.
+        baseline: Fit,
+        /// What the fresh run inferred.
+        current: Fit,
+        /// How far the parameter moved, signed: positive is faster growth. In
+        /// the exponent's units for `O(n^m)`; as a share of the baseline's
+        /// growth rate `ln c` for `O(c^n)`.
+        by: f64,
+    },
+
+    /// The complexity is unchanged, but the fresh run supports it much less
+    /// firmly than the baseline did. Usually noisier measurements rather than
+    /// different code, and worth knowing before trusting the next verdict.
+    LessConfident {
+        /// The baseline's confidence.
+        baseline: f64,
+        /// The fresh run's confidence.
+        current: f64,
+    },
+}
+
+impl Verdict {
+    /// Whether the cost now grows faster than the baseline's did.
+    pub fn is_regression(&self) -> bool {
+        match self {
+            Verdict::Grew { .. } => true,
+            Verdict::Drifted { by, .. } => *by > 0.0,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Unchanged => write!(f, "complexity unchanged"),
+            Verdict::Grew { baseline, current } => {
+                write!(f, "complexity grew from {baseline} to {current}")
+            }
+            Verdict::Shrank { baseline, current } => {
+                write!(f, "complexity shrank from {baseline} to {current}")
+            }
+            Verdict::Drifted {
+                baseline,
+                current,
+                by,
+            } => write!(f, "{baseline} drifted to {current}, by {by:+.2}"),
+            Verdict::LessConfident { baseline, current } => write!(
+                f,
+                "complexity unchanged, but confidence fell from {baseline:.2} to {current:.2}"
+            ),
+        }
+    }
+}
+
+/// Tolerances for telling a change in complexity from a change in noise.
+///
+/// # Example
+/// ```
+/// use big_o::{Comparison, Verdict};
+///
+/// let baseline = big_o::infer_complexity(&[(1., 1.), (10., 10.), (100., 100.), (1000., 1000.)]).unwrap();
+/// let current = big_o::infer_complexity(&[(1., 1.), (10., 100.), (100., 1e4), (1000., 1e6)]).unwrap();
+///
+/// let verdict = Comparison::new().exponent(0.05).compare(&current, &baseline);
+///
+/// assert!(matches!(verdict, Verdict::Grew { .. }));
+/// assert!(verdict.is_regression());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    exponent: f64,
+    confidence: f64,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Comparison {
+    /// Tolerates an exponent moving by 0.1 and confidence falling by 0.2.
+    pub fn new() -> Self {
+        Self {
+            exponent: EXPONENT_TOLERANCE,
+            confidence: CONFIDENCE_TOLERANCE,
+        }
+    }
+
+    /// Tolerates a fitted exponent moving by up to `tolerance`, a fitted base's
+    /// growth rate `ln c` by up to that share of itself, and a fitted power law
+    /// or custom shape this close on the degree scale to another complexity
+    /// being reported as one. Two distinct named models are never within it.
+    pub fn exponent(mut self, tolerance: f64) -> Self {
+        self.exponent = tolerance;
+        self
+    }
+
+    /// Tolerates confidence falling by up to `tolerance`.
+    pub fn confidence(mut self, tolerance: f64) -> Self {
+        self.confidence = tolerance;
+        self
+    }
+
+    /// Classifies how `current` differs from `baseline`.
+    pub fn compare(&self, current: &Inference, baseline: &Inference) -> Verdict {
+        let (now, then) = (current.best, baseline.best);
+
+        if let Some(by) = drift(&now, &then) {
+            if by.abs() > self.exponent {
+                return Verdict::Drifted {
+                    baseline: then,
+                    current: now,
+                    by,
+                };
+            }
+        } else if !is_approximate(&now) && !is_approximate(&then)
+            || (now.degree() - then.degree()).abs() > self.exponent
+            || now.degree().is_infinite() != then.degree().is_infinite()
+        {
+            // Different models: two named ones, which are different classes
+            // however close their degrees, or far enough apart on the degree
+            // scale that the difference is not a fitted exponent landing either
+            // side of a named one.
+            match now.partial_cmp(&then) {
+                Some(Ordering::Greater) => {
+                    return Verdict::Grew {
+                        baseline: then,
+                        current: now,
+                    }
+                }
+                Some(Ordering::Less) => {
+                    return Verdict::Shrank {
+                        baseline: then,
+                        current: now,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if baseline.confidence - current.confidence > self.confidence {
+            return Verdict::LessConfident {
+                baseline: baseline.confidence,
+                current: current.confidence,
+            };
+        }
+        Verdict::Unchanged
+    }
+}
+
+/// Whether `fit`'s place on the degree scale is an estimate rather than a
+/// class: a fitted exponent, or the degree a custom shape's author put it at.
+fn is_approximate(fit: &Fit) -> bool {
+    matches!(fit.model, Model::Polynomial | Model::Custom(_))
+}
+
+/// How far the free parameter moved between two fits of the same model that
+/// has one, or `None` if they are not such a pair: the exponent's change, or
+/// the growth rate's relative to the baseline's. A baseline that did not grow
+/// at all has no rate to be relative to, and its change is absolute.
+fn drift(current: &Fit, baseline: &Fit) -> Option<f64> {
+    match (current.params, baseline.params) {
+        (
+            ModelParams::Polynomial { power: now, .. },
+            ModelParams::Polynomial { power: then, .. },
+        ) => Some(now - then),
+        (
+            ModelParams::Exponential { base: now, .. },
+            ModelParams::Exponential { base: then, .. },
+        ) => {
+            let (now, then) = (now.ln(), then.ln());
+            Some(match then > 0.0 {
+                true => now / then - 1.0,
+                false => now - then,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Inference {
+    /// Classifies how this inference differs from `baseline`, at the default
+    /// tolerances. See [`Comparison`] to set them.
+    ///
+    /// ```
+    /// let data = [(1., 1.), (10., 10.), (100., 100.), (1000., 1000.)];
+    /// let baseline = big_o::infer_complexity(&data).unwrap();
+    /// let current = big_o::infer_complexity(&data).unwrap();
+    ///
+    /// assert_eq!(current.compare(&baseline), big_o::Verdict::Unchanged);
+    /// ```
+    pub fn compare(&self, baseline: &Inference) -> Verdict {
+        Comparison::new().compare(self, baseline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::fit_of;
+
+    fn inference(model: Model, params: ModelParams, confidence: f64) -> Inference {
+        let best = fit_of(model, params, 0.0);
+        Inference {
+            best,
+            all: vec![best],
+            criteria: Vec::new(),
+            confidence,
+            votes: Vec::new(),
+            range: None,
+            influence: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn linear() -> Inference {
+        inference(
+            Model::Linear,
+            ModelParams::Linear {
+                gain: 1.0,
+                offset: 0.0,
+            },
+            1.0,
+        )
+    }
+
+    fn quadratic() -> Inference {
+        inference(
+            Model::Quadratic,
+            ModelParams::Quadratic {
+                gain: 1.0,
+                offset: 0.0,
+            },
+            1.0,
+        )
+    }
+
+    fn polynomial(power: f64) -> Inference {
+        inference(
+            Model::Polynomial,
+            ModelParams::Polynomial {
+                gain: 1.0,
+                power,
+                offset: 0.0,
+            },
+            1.0,
+        )
+    }
+
+    #[test]
+    fn a_faster_growing_class_is_a_regression() {
+        let verdict = quadratic().compare(&linear());
+
+        assert!(matches!(verdict, Verdict::Grew { .. }));
+        assert!(verdict.is_regression());
+        assert_eq!(verdict.to_string(), "complexity grew from O(n) to O(n^2)");
+    }
+
+    #[test]
+    fn a_slower_growing_class_is_not() {
+        let verdict = linear().compare(&quadratic());
+
+        assert!(matches!(verdict, Verdict::Shrank { .. }));
+        assert!(!verdict.is_regression());
+    }
+
+    #[test]
+    fn a_different_name_for_the_same_growth_is_unchanged() {
+        // A fitted exponent landing just past a named model is noise, not a
+        // change of class.
+        assert_eq!(polynomial(1.04).compare(&linear()), Verdict::Unchanged);
+        assert_eq!(linear().compare(&polynomial(0.97)), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn named_models_differ_however_close_their_degrees() {
+        let log_squared = inference(
+            Model::LogSquared,
+            ModelParams::LogSquared {
+                gain: 1.0,
+                offset: 0.0,
+            },
+            1.0,
+        );
+        let cube_root = inference(
+            Model::CubeRoot,
+            ModelParams::CubeRoot {
+                gain: 1.0,
+                offset: 0.0,
+            },
+            1.0,
+        );
+
+        assert!(matches!(
+            cube_root.compare(&log_squared),
+            Verdict::Grew { .. }
+        ));
+        assert!(matches!(
+            log_squared.compare(&cube_root),
+            Verdict::Shrank { .. }
+        ));
+    }
+
+    #[test]
+    fn an_exponent_that_moves_too_far_has_drifted() {
+        let verdict = polynomial(1.7).compare(&polynomial(1.5));
+
+        match verdict {
+            Verdict::Drifted { by, .. } => assert!((by - 0.2).abs() < 1e-9),
+            other => panic!("expected a drift, got {other:?}"),
+        }
+        assert!(verdict.is_regression());
+        assert!(!polynomial(1.3).compare(&polynomial(1.5)).is_regression());
+        assert_eq!(
+            polynomial(1.53).compare(&polynomial(1.5)),
+            Verdict::Unchanged
+        );
+    }
+
+    #[test]
+    fn a_fall_in_confidence_is_reported_when_nothing_else_changed() {
+        let mut shaky = linear();
+        shaky.confidence = 0.6;
+
+        assert_eq!(
+            shaky.compare(&linear()),
+            Verdict::LessConfident {
+                baseline: 1.0,
+                current: 0.6
+            }
+        );
+        assert!(!shaky.compare(&linear()).is_regression());
+        assert!(
+            matches!(quadratic().compare(&shaky), Verdict::Grew { .. }),
+            "a change of class outranks a change of confidence"
+        );
+    }
+
+    fn exponential(base: f64) -> Inference {
+        inference(
+            Model::Exponential,
+            ModelParams::Exponential {
+                gain: 1.0,
+                base,
+                offset: 0.0,
+            },
+            1.0,
+        )
+    }
+
+    #[test]
+    fn a_base_drifts_by_its_growth_rate() {
+        // 1.01^n to 1.09^n is some three thousandfold at n = 1000.
+        match exponential(1.09).compare(&exponential(1.01)) {
+            Verdict::Drifted { by, .. } => assert!(by > 7.0, "{by}"),
+            other => panic!("expected a drift, got {other:?}"),
+        }
+        assert_eq!(
+            exponential(10.2).compare(&exponential(10.0)),
+            Verdict::Unchanged
+        );
+        assert!(!exponential(1.5).compare(&exponential(2.0)).is_regression());
+    }
+
+    #[test]
+    fn an_exponential_is_never_within_tolerance_of_a_polynomial() {
+        let exponential = exponential(2.0);
+
+        assert!(matches!(
+            exponential.compare(&polynomial(3.0)),
+            Verdict::Grew { .. }
+        ));
+        assert!(Comparison::new()
+            .exponent(f64::MAX)
+            .compare(&exponential, &polynomial(3.0))
+            .is_regression());
+    }
+}
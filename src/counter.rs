@@ -0,0 +1,152 @@
+//! Counting the operations a routine performs, for an exact abstract cost.
+//!
+//! Time mixes the algorithm with the machine: caches, branch predictors and
+//! whatever else is running. A count of comparisons, swaps or node visits is
+//! the algorithm alone, the same on every run and every machine, so the only
+//! thing left for the fit to tell apart is the models. The routine under test
+//! counts by hand, through a [`Counter`], and the harness reads the count.
+
+use std::cell::Cell;
+
+thread_local! {
+    /// Everything counted on this thread so far. Wraps rather than overflows:
+    /// only differences are ever read, and those come out right either way.
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A handle the code under test increments once per operation it performs.
+///
+/// The count is per thread: a handle moved to another thread counts there, and
+/// a measurement on one thread never sees another's operations. Every enabled
+/// handle on a thread adds to the same count, so count one kind of operation
+/// at a time. A [`disabled`](Self::disabled) handle counts nothing, and once
+/// its calls are inlined the optimizer removes them, so instrumented code can
+/// take a `Counter` in production as well as under measurement.
+///
+/// # Example
+/// ```
+/// use big_o::Counter;
+///
+/// fn contains(sorted: &[u32], key: u32, counter: Counter) -> bool {
+///     sorted
+///         .binary_search_by(|probe| {
+///             counter.increment();
+///             probe.cmp(&key)
+///         })
+///         .is_ok()
+/// }
+///
+/// let sorted: Vec<u32> = (0..1_024).collect();
+/// let counter = Counter::new();
+///
+/// let (found, comparisons) = counter.measure(|| contains(&sorted, 700, counter));
+///
+/// assert!(found);
+/// assert!(comparisons <= 11);
+/// assert!(!contains(&sorted, 2_000, Counter::disabled()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counter {
+    enabled: bool,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Counter {
+    /// A handle that counts.
+    pub const fn new() -> Self {
+        Self { enabled: true }
+    }
+
+    /// A handle that counts nothing.
+    pub const fn disabled() -> Self {
+        Self { enabled: false }
+    }
+
+    /// Whether this handle counts.
+    pub const fn is_enabled(self) -> bool {
+        self.enabled
+    }
+
+    /// Counts one operation.
+    #[inline]
+    pub fn increment(self) {
+        self.add(1);
+    }
+
+    /// Counts `operations` operations at once.
+    #[inline]
+    pub fn add(self, operations: u64) {
+        if self.enabled {
+            COUNT.with(|count| count.set(count.get().wrapping_add(operations)));
+        }
+    }
+
+    /// Runs `routine` and reports how many operations enabled handles counted
+    /// on this thread while it ran, along with what it returned.
+    ///
+    /// Measurements nest: an outer one includes whatever an inner one counted.
+    pub fn measure<R>(self, routine: impl FnOnce() -> R) -> (R, u64) {
+        let start = COUNT.with(Cell::get);
+        let result = routine();
+        (result, COUNT.with(Cell::get).wrapping_sub(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_what_the_routine_adds() {
+        let counter = Counter::new();
+
+        let ((), count) = counter.measure(|| {
+            for _ in 0..10 {
+                counter.increment();
+            }
+            counter.add(5);
+        });
+
+        assert_eq!(count, 15);
+    }
+
+    #[test]
+    fn a_disabled_handle_counts_nothing() {
+        let counter = Counter::disabled();
+
+        let ((), count) = counter.measure(|| counter.add(1_000));
+
+        assert_eq!(count, 0);
+        assert!(!counter.is_enabled());
+    }
+
+    #[test]
+    fn an_outer_measurement_includes_an_inner_one() {
+        let counter = Counter::new();
+
+        let (inner, outer) = counter.measure(|| {
+            counter.add(3);
+            let ((), inner) = counter.measure(|| counter.add(4));
+            inner
+        });
+
+        assert_eq!((inner, outer), (4, 7));
+    }
+
+    #[test]
+    fn another_thread_counts_apart() {
+        let counter = Counter::new();
+
+        let ((), count) = counter.measure(|| {
+            std::thread::spawn(move || counter.add(100)).join().unwrap();
+            counter.increment();
+        });
+
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,420 @@
+//! Where two fitted cost curves cross: the input size at which one
+//! implementation overtakes another.
+//!
+//! Two algorithms for the same job rarely differ by a constant factor. An
+//! insertion sort beats a merge sort on short inputs and loses on long ones, and
+//! the size at which that flips is the threshold a hybrid switches at. Both
+//! curves are fitted already; the crossover is where their difference changes
+//! sign.
+
+use crate::analysis::Inference;
+use crate::fit::Fit;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// How many decades either side of the measured range the crossings are
+/// searched for. A crossing further out is a guess about inputs three orders
+/// of magnitude from anything that was timed, and not worth reporting.
+const REACH: f64 = 3.0;
+
+/// Points per decade at which the two curves are compared before a change of
+/// sign is bisected. Two crossings closer together than a thirty-second of a
+/// decade are not told apart, and cancel out.
+const STEPS_PER_DECADE: f64 = 32.0;
+
+/// Bisection steps per crossing, each halving the bracket in log space.
+/// Enough to narrow a bracket of one step to a relative width below `1e-12`.
+const BISECTIONS: usize = 48;
+
+/// Where two cost curves cross, and which is cheaper in between.
+///
+/// The curves are compared over the measured input sizes and three decades
+/// either side, for positive input sizes only.
+///
+/// # Example
+/// ```
+/// use big_o::Cheaper;
+///
+/// let sizes = || (1..=12).map(|k| 2f64.powi(k));
+/// let insertion: Vec<(f64, f64)> = sizes().map(|n| (n, 0.25 * n * n)).collect();
+/// let merge: Vec<(f64, f64)> = sizes().map(|n| (n, 3.0 * n * n.log2())).collect();
+///
+/// let insertion = big_o::infer_complexity(&insertion).unwrap();
+/// let merge = big_o::infer_complexity(&merge).unwrap();
+/// let crossover = insertion.crossover(&merge);
+///
+/// // Insertion sort is cheaper up to about 75 elements, merge sort beyond.
+/// let threshold = crossover.crossings.iter().find(|c| !c.extrapolated).unwrap();
+/// assert!((threshold.at - 74.7).abs() < 0.5);
+/// assert_eq!(crossover.segments.last().unwrap().cheaper, Cheaper::Second);
+///
+/// // Below one element, the fitted `n log n` goes negative and "wins": a
+/// // crossing no measurement supports, and flagged as such.
+/// assert!(crossover.crossings[0].extrapolated);
+/// assert_eq!(
+///     format!("{crossover:.1}"),
+///     "the second is cheaper up to 1.1 (extrapolated), \
+///      then the first is cheaper up to 74.7, \
+///      then the second is cheaper"
+/// );
+/// ```
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Crossover {
+    /// Every input size at which the cheaper curve changes, ascending.
+    pub crossings: Vec<Crossing>,
+
+    /// The searched input sizes, split at the crossings: one more segment than
+    /// there are crossings, ascending. Empty when there was nothing to search.
+    pub segments: Vec<Segment>,
+}
+
+/// One input size at which the two curves cross.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Crossing {
+    /// The input size at which the two costs are equal.
+    pub at: f64,
+
+    /// Whether the crossing lies outside the input sizes measured for either
+    /// curve, where at least one of them is extrapolated rather than measured.
+    pub extrapolated: bool,
+}
+
+/// A run of input sizes over which the same curve is the cheaper one.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Segment {
+    /// The smallest input size in the segment.
+    pub from: f64,
+
+    /// The largest input size in the segment.
+    pub to: f64,
+
+    /// Which curve costs less over it.
+    pub cheaper: Cheaper,
+}
+
+/// Which of two curves costs less.
+///
+/// Non-exhaustive: a later release may tell apart curves that are equal within
+/// their uncertainty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Cheaper {
+    /// The curve the crossover was asked of.
+    First,
+
+    /// The curve it was compared with.
+    Second,
+
+    /// Both cost the same.
+    Neither,
+}
+
+impl fmt::Display for Cheaper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Cheaper::First => "the first is cheaper",
+            Cheaper::Second => "the second is cheaper",
+            Cheaper::Neither => "both cost the same",
+        })
+    }
+}
+
+/// The segments in turn, each up to the crossing that ends it:
+/// `the first is cheaper up to 74.7, then the second is cheaper`. The
+/// formatter's options apply to the input sizes.
+impl fmt::Display for Crossover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return f.write_str("no input sizes to compare over");
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", then ")?;
+            }
+            write!(f, "{}", segment.cheaper)?;
+            if let Some(crossing) = self.crossings.get(i) {
+                f.write_str(" up to ")?;
+                fmt::Display::fmt(&crossing.at, f)?;
+                if crossing.extrapolated {
+                    f.write_str(" (extrapolated)")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Fit {
+    /// Where this fit's cost curve crosses `other`'s, given the input sizes
+    /// both were `measured` over. Crossings outside them are flagged as
+    /// extrapolated.
+    ///
+    /// A range that starts at zero or below is searched from a thousandth of
+    /// its end, as the logarithmic models have no value at zero. A range with
+    /// no finite end has nothing to search, and the search stops at the
+    /// largest and smallest sizes an `f64` holds, however far past the range
+    /// that falls short of reaching.
+    ///
+    /// ```
+    /// let fast_start = big_o::infer_complexity(&[(1., 10.), (10., 100.), (100., 1000.)])
+    ///     .unwrap()
+    ///     .best;
+    /// let slow_start = big_o::infer_complexity(&[(1., 500.), (10., 600.), (100., 700.)])
+    ///     .unwrap()
+    ///     .best;
+    ///
+    /// let crossover = fast_start.crossover(&slow_start, 1.0..=100.0);
+    /// assert_eq!(crossover.crossings.len(), 1);
+    /// assert!(!crossover.crossings[0].extrapolated);
+    /// ```
+    pub fn crossover(&self, other: &Fit, measured: RangeInclusive<f64>) -> Crossover {
+        let (start, end) = measured.clone().into_inner();
+        let start = match start > 0.0 {
+            true => start,
+            false => end / 10f64.powf(REACH),
+        };
+        match start > 0.0 && end >= start && end.is_finite() {
+            true => search(self, other, start, end, &measured),
+            false => Crossover {
+                crossings: Vec::new(),
+                segments: Vec::new(),
+            },
+        }
+    }
+}
+
+impl Inference {
+    /// Where the cost curve of this inference's [`best`](Self::best) fit
+    /// crosses `other`'s.
+    ///
+    /// A crossing is extrapolated unless both inferences measured input sizes
+    /// around it. Without a [`range`](Self::range) on either — inferences
+    /// stored before ranges were reported — there is nothing to search.
+    pub fn crossover(&self, other: &Inference) -> Crossover {
+        let (Some((start, end)), Some((other_start, other_end))) = (self.range, other.range) else {
+            return Crossover {
+                crossings: Vec::new(),
+                segments: Vec::new(),
+            };
+        };
+        let searched = Fit::crossover(
+            &self.best,
+            &other.best,
+            start.min(other_start)..=end.max(other_end),
+        );
+        let measured = start.max(other_start)..=end.min(other_end);
+        Crossover {
+            crossings: searched
+                .crossings
+                .into_iter()
+                .map(|crossing| Crossing {
+                    extrapolated: !measured.contains(&crossing.at),
+                    ..crossing
+                })
+                .collect(),
+            ..searched
+        }
+    }
+}
+
+/// Finds the crossings of `first` and `second` over `start..=end` and
+/// [`REACH`] decades either side of it.
+fn search(
+    first: &Fit,
+    second: &Fit,
+    start: f64,
+    end: f64,
+    measured: &RangeInclusive<f64>,
+) -> Crossover {
+    // Held to the sizes an `f64` holds: past them, the span and the number of
+    // steps across it would be infinite.
+    let low = (start / 10f64.powf(REACH)).max(f64::MIN_POSITIVE);
+    let high = (end * 10f64.powf(REACH)).min(f64::MAX);
+    let decades = high.log10() - low.log10();
+    let steps = (decades * STEPS_PER_DECADE).ceil().max(1.0) as usize;
+    let at = |step: usize| match step {
+        0 => low,
+        step if step == steps => high,
+        step => 10f64.powf(low.log10() + decades * step as f64 / steps as f64),
+    };
+
+    // The last input size at which one curve was strictly cheaper. Points
+    // where the costs are equal or cannot be compared neither open nor close
+    // a bracket, so a curve that touches the other without passing it is not
+    // a crossing.
+    let mut last: Option<(f64, Cheaper)> = None;
+    let mut crossings = Vec::new();
+    for step in 0..=steps {
+        let x = at(step);
+        let cheaper = match cheaper(first, second, x) {
+            Some(Cheaper::Neither) | None => continue,
+            Some(cheaper) => cheaper,
+        };
+        if let Some((before, previously)) = last {
+            if previously != cheaper {
+                let at = bisect(first, second, before, x, previously);
+                crossings.push(Crossing {
+                    at,
+                    extrapolated: !measured.contains(&at),
+                });
+            }
+        }
+        last = Some((x, cheaper));
+    }
+
+    let mut bounds = vec![low];
+    bounds.extend(crossings.iter().map(|crossing| crossing.at));
+    bounds.push(high);
+    let segments = bounds
+        .windows(2)
+        .map(|pair| Segment {
+            from: pair[0],
+            to: pair[1],
+            cheaper: cheaper(first, second, midpoint(pair[0], pair[1])).unwrap_or(Cheaper::Neither),
+        })
+        .collect();
+
+    Crossover {
+        crossings,
+        segments,
+    }
+}
+
+/// Which fit costs less at `x`, or `None` where the two cannot be compared.
+fn cheaper(first: &Fit, second: &Fit, x: f64) -> Option<Cheaper> {
+    Some(match first.evaluate(x).partial_cmp(&second.evaluate(x))? {
+        std::cmp::Ordering::Less => Cheaper::First,
+        std::cmp::Ordering::Greater => Cheaper::Second,
+        std::cmp::Ordering::Equal => Cheaper::Neither,
+    })
+}
+
+/// Narrows `[low, high]`, where `at_low` is cheaper at `low` and the other at
+/// `high`, to the input size at which they cost the same.
+fn bisect(first: &Fit, second: &Fit, mut low: f64, mut high: f64, at_low: Cheaper) -> f64 {
+    for _ in 0..BISECTIONS {
+        let middle = midpoint(low, high);
+        match cheaper(first, second, middle) {
+            Some(cheaper) if cheaper == at_low => low = middle,
+            Some(Cheaper::Neither) | None => return middle,
+            Some(_) => high = middle,
+        }
+    }
+    midpoint(low, high)
+}
+
+/// The geometric mean of `low` and `high`, which does not overflow where their
+/// product would.
+fn midpoint(low: f64, high: f64) -> f64 {
+    low.sqrt() * high.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::{fit_of, ModelParams};
+    use crate::model::Model;
+
+    fn line(gain: f64, offset: f64) -> Fit {
+        fit_of(Model::Linear, ModelParams::Linear { gain, offset }, 0.0)
+    }
+
+    fn square(gain: f64, offset: f64) -> Fit {
+        fit_of(
+            Model::Quadratic,
+            ModelParams::Quadratic { gain, offset },
+            0.0,
+        )
+    }
+
+    #[test]
+    fn finds_where_a_square_overtakes_a_line() {
+        let crossover = square(1.0, 0.0).crossover(&line(100.0, 0.0), 1.0..=1000.0);
+
+        assert_eq!(crossover.crossings.len(), 1);
+        assert!((crossover.crossings[0].at - 100.0).abs() < 1e-9);
+        assert!(!crossover.crossings[0].extrapolated);
+        let cheaper: Vec<Cheaper> = crossover.segments.iter().map(|s| s.cheaper).collect();
+        assert_eq!(cheaper, [Cheaper::First, Cheaper::Second]);
+        assert_eq!(crossover.segments[0].from, 1e-3);
+        assert_eq!(crossover.segments[1].to, 1e6);
+    }
+
+    #[test]
+    fn finds_every_crossing_in_order() {
+        // n^2 + 500 against 60n: equal at 10 and at 50.
+        let crossover = square(1.0, 500.0).crossover(&line(60.0, 0.0), 1.0..=1000.0);
+
+        let at: Vec<f64> = crossover.crossings.iter().map(|c| c.at).collect();
+        assert_eq!(at.len(), 2);
+        assert!((at[0] - 10.0).abs() < 1e-9 && (at[1] - 50.0).abs() < 1e-9);
+        let cheaper: Vec<Cheaper> = crossover.segments.iter().map(|s| s.cheaper).collect();
+        assert_eq!(cheaper, [Cheaper::Second, Cheaper::First, Cheaper::Second]);
+    }
+
+    #[test]
+    fn flags_a_crossing_past_the_measured_sizes_as_extrapolated() {
+        let crossover = square(1.0, 0.0).crossover(&line(100.0, 0.0), 1.0..=50.0);
+
+        assert!(crossover.crossings[0].extrapolated);
+        assert_eq!(
+            format!("{crossover:.0}"),
+            "the first is cheaper up to 100 (extrapolated), then the second is cheaper"
+        );
+    }
+
+    #[test]
+    fn curves_that_never_cross_have_one_segment() {
+        let crossover = line(2.0, 1.0).crossover(&line(3.0, 1.0), 1.0..=100.0);
+
+        assert!(crossover.crossings.is_empty());
+        assert_eq!(crossover.segments.len(), 1);
+        assert_eq!(crossover.segments[0].cheaper, Cheaper::First);
+    }
+
+    #[test]
+    fn identical_curves_cost_the_same_throughout() {
+        let crossover = line(2.0, 1.0).crossover(&line(2.0, 1.0), 1.0..=100.0);
+
+        assert!(crossover.crossings.is_empty());
+        assert_eq!(crossover.segments[0].cheaper, Cheaper::Neither);
+        assert_eq!(crossover.to_string(), "both cost the same");
+    }
+
+    #[test]
+    fn an_empty_range_has_nothing_to_search() {
+        let crossover = line(2.0, 1.0).crossover(&line(3.0, 0.0), 0.0..=0.0);
+
+        assert!(crossover.segments.is_empty());
+        assert_eq!(crossover.to_string(), "no input sizes to compare over");
+    }
+
+    #[test]
+    fn a_range_without_a_finite_end_has_nothing_to_search() {
+        let crossover = line(2.0, 0.0).crossover(&square(1.0, 0.0), 1.0..=f64::INFINITY);
+
+        assert!(crossover.segments.is_empty());
+    }
+
+    #[test]
+    fn a_range_reaching_past_the_largest_f64_is_searched_up_to_it() {
+        let crossover = line(2.0, 0.0).crossover(&square(1.0, 0.0), 1.0..=1e306);
+
+        assert_eq!(crossover.crossings.len(), 1);
+        assert!((crossover.crossings[0].at - 2.0).abs() < 1e-9);
+        assert_eq!(crossover.segments.last().unwrap().to, f64::MAX);
+    }
+}
@@ -2,11 +2,19 @@
 //!
 //! Benchmark output is rarely one `y` per `x`: the same input size is usually
 //! measured several times, and a run that was descheduled shows up as a single
-//! large outlier. Collapsing each input size to its median absorbs those
-//! outliers without discarding the repetition, and it leaves the fitters with
-//! the one shape they can rely on — strictly increasing, finite `x`.
+//! large outlier. Collapsing each input size to one cost — its median, unless
+//! the analysis asks for another [`Aggregation`] — absorbs those outliers
+//! without discarding the repetition, and it leaves the fitters with the one
+//! shape they can rely on — strictly increasing, finite `x`. How far the
+//! repeats scattered is kept beside the cost they collapsed to.
+//!
+//! An analysis that asks for it also drops the repeats that lie too far from
+//! the rest of their size before collapsing them, and keeps a record of what it
+//! dropped.
 
+use crate::aggregation::Aggregation;
 use crate::linalg;
+use crate::robust;
 
 /// Smallest sample a fit can be meaningfully inferred from.
 ///
@@ -15,10 +23,55 @@ use crate::linalg;
 /// right. Three is the first size at which the models can disagree.
 pub(crate) const MIN_POINTS: usize = 3;
 
-/// Measurements grouped by input size, one median `y` per distinct `x`,
+/// Fewest measurements at every input size before their scatter is trusted to
+/// weigh the sizes against each other.
+///
+/// A variance estimated from `n` measurements is itself uncertain by a share
+/// of about `sqrt(2 / (n - 1))`: more than half below ten, where a size that
+/// happened to scatter little would be given several times its due. The
+/// default five repeats of a [`Harness`](crate::Harness) are not enough.
+pub(crate) const MIN_REPEATS: usize = 10;
+
+/// Smallest standard error credited to an aggregated cost, as a share of it.
+///
+/// Repeats that agree exactly — a deterministic count, or a clock too coarse
+/// to tell them apart — have no variance, and would otherwise be given an
+/// infinite weight. A hundredth of a percent is finer than any timing resolves.
+const FINEST_RESOLUTION: f64 = 1e-4;
+
+/// Measurements grouped by input size, one aggregated `y` per distinct `x`,
 /// sorted by ascending `x`.
 pub(crate) struct Sample {
     points: Vec<(f64, f64)>,
+    spread: Vec<Spread>,
+    /// The input size of each measurement rejected as an outlier, ascending.
+    removed: Vec<f64>,
+}
+
+/// How the measurements one input size was collapsed from were scattered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Spread {
+    /// How many finite measurements there were.
+    pub(crate) repeats: usize,
+
+    /// Their sample variance, or zero for a single measurement.
+    pub(crate) variance: f64,
+
+    /// The variance of the cost they were aggregated to, as
+    /// [`Aggregation::variance_factor`] estimates it.
+    pub(crate) error: f64,
+}
+
+impl Spread {
+    /// The spread of `values`, before they are aggregated by `aggregation`.
+    fn of(values: &[f64], aggregation: Aggregation) -> Spread {
+        let variance = linalg::standard_deviation(values).map_or(0.0, |sd| sd * sd);
+        Spread {
+            repeats: values.len(),
+            variance,
+            error: aggregation.variance_factor() * variance / values.len().max(1) as f64,
+        }
+    }
 }
 
 impl Sample {
@@ -27,6 +80,96 @@ impl Sample {
         &self.points
     }
 
+    /// The spread of the measurements behind each point, in the order of
+    /// [`points`](Self::points).
+    #[cfg(test)]
+    pub(crate) fn spread(&self) -> &[Spread] {
+        &self.spread
+    }
+
+    /// The variance of each point's cost, when every point was measured often
+    /// enough to estimate it — at least [`MIN_REPEATS`] times — and the
+    /// measurements scattered at all. Each is held to at least
+    /// [`FINEST_RESOLUTION`] of its cost, and that cost to at least `floor`:
+    /// a size whose repeats all cost exactly nothing — no allocations, no
+    /// operations counted — would otherwise have no variance at all, and an
+    /// infinite weight. `None` for a `floor` of zero, which gives no scale to
+    /// hold a variance to.
+    pub(crate) fn variances(&self, floor: f64) -> Option<Vec<f64>> {
+        let known = floor > 0.0
+            && self
+                .spread
+                .iter()
+                .all(|spread| spread.repeats >= MIN_REPEATS)
+            && self.spread.iter().any(|spread| spread.error > 0.0);
+        known.then(|| {
+            self.points
+                .iter()
+                .zip(&self.spread)
+                .map(|(&(_, y), spread)| {
+                    spread
+                        .error
+                        .max((FINEST_RESOLUTION * y.abs().max(floor)).powi(2))
+                })
+                .collect()
+        })
+    }
+
+    /// The standard error of each point's cost as estimated from its repeats,
+    /// as a share of the cost. Zero for a point measured once.
+    pub(crate) fn relative_standard_errors(&self) -> Vec<f64> {
+        self.points
+            .iter()
+            .zip(&self.spread)
+            .map(|(&(_, y), spread)| match y != 0.0 {
+                true => spread.error.sqrt() / y.abs(),
+                false => 0.0,
+            })
+            .collect()
+    }
+
+    /// The input size of each measurement rejected as an outlier, ascending:
+    /// a size appears once per measurement dropped from it.
+    pub(crate) fn removed(&self) -> &[f64] {
+        &self.removed
+    }
+
+    /// The points at `indices`, each once and in order, with their spread.
+    /// Indices past the end are ignored.
+    ///
+    /// A selection is of points that survived preparation, so it has no
+    /// outliers of its own to report.
+    pub(crate) fn select(&self, indices: impl IntoIterator<Item = usize>) -> Sample {
+        let mut indices: Vec<usize> = indices
+            .into_iter()
+            .filter(|&i| i < self.points.len())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        Sample {
+            points: indices.iter().map(|&i| self.points[i]).collect(),
+            spread: indices.iter().map(|&i| self.spread[i]).collect(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// This sample without the points at `indices`, every measurement behind
+    /// them added to those [`removed`](Self::removed).
+    pub(crate) fn without(&self, indices: &[usize]) -> Sample {
+        let mut sample = self.select((0..self.points.len()).filter(|i| !indices.contains(i)));
+        sample.removed = self.removed.clone();
+        for &i in indices.iter().filter(|&&i| i < self.points.len()) {
+            let (x, _) = self.points[i];
+            sample
+                .removed
+                .extend(std::iter::repeat(x).take(self.spread[i].repeats));
+        }
+        sample
+            .removed
+            .sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        sample
+    }
+
     /// Whether cost falls overall as the input grows.
     ///
     /// Compares the means of the first and last third rather than adjacent
@@ -67,6 +210,12 @@ impl Sample {
         !deltas.is_empty() && reversals * 3 > deltas.len()
     }
 
+    /// The smallest and largest input size, or `None` for an empty sample.
+    pub(crate) fn range(&self) -> Option<(f64, f64)> {
+        let (&(first, _), &(last, _)) = (self.points.first()?, self.points.last()?);
+        Some((first, last))
+    }
+
     /// How many decades of input size the sample spans.
     pub(crate) fn decades(&self) -> f64 {
         let (Some(&(first, _)), Some(&(last, _))) = (self.points.first(), self.points.last())
@@ -82,9 +231,14 @@ impl Sample {
 
 /// Prepares raw measurements for fitting.
 ///
-/// Drops non-finite points, collapses repeated `x` to their median `y`, and
-/// sorts by ascending `x`.
-pub(crate) fn prepare(data: &[(f64, f64)]) -> Sample {
+/// Drops non-finite points, collapses repeated `x` to one `y` by `aggregation`,
+/// and sorts by ascending `x`. With `outliers`, first drops every repeat more
+/// than that many deviations from the median of its size; see [`inliers`].
+pub(crate) fn prepare(
+    data: &[(f64, f64)],
+    aggregation: Aggregation,
+    outliers: Option<f64>,
+) -> Sample {
     let mut finite: Vec<(f64, f64)> = data
         .iter()
         .copied()
@@ -92,15 +246,33 @@ pub(crate) fn prepare(data: &[(f64, f64)]) -> Sample {
         .collect();
     finite.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
-    let mut points: Vec<(f64, f64)> = Vec::with_capacity(finite.len());
+    let mut sample = Sample {
+        points: Vec::with_capacity(finite.len()),
+        spread: Vec::with_capacity(finite.len()),
+        removed: Vec::new(),
+    };
+    let mut collapse = |x: f64, group: &mut Vec<f64>| {
+        if let Some(deviations) = outliers {
+            let kept = inliers(group, deviations);
+            sample
+                .removed
+                .extend(std::iter::repeat(x).take(group.len() - kept.len()));
+            *group = kept;
+        }
+        let spread = Spread::of(group, aggregation);
+        if let Some(y) = aggregation.summarize(group) {
+            sample.points.push((x, y));
+            sample.spread.push(spread);
+        }
+    };
     let mut group: Vec<f64> = Vec::new();
     let mut current: Option<f64> = None;
     for (x, y) in finite {
         match current {
             Some(seen) if seen == x => group.push(y),
             _ => {
-                if let (Some(seen), Some(median)) = (current, linalg::median(&mut group)) {
-                    points.push((seen, median));
+                if let Some(seen) = current {
+                    collapse(seen, &mut group);
                 }
                 group.clear();
                 group.push(y);
@@ -108,17 +280,48 @@ pub(crate) fn prepare(data: &[(f64, f64)]) -> Sample {
             }
         }
     }
-    if let (Some(seen), Some(median)) = (current, linalg::median(&mut group)) {
-        points.push((seen, median));
+    if let Some(seen) = current {
+        collapse(seen, &mut group);
     }
 
-    Sample { points }
+    sample
+}
+
+/// The values no more than `deviations` deviations from their median.
+///
+/// A deviation is the median absolute deviation scaled to the standard
+/// deviation it implies under Gaussian noise, the scale the robust losses
+/// weigh residuals on, so `3.0` means what three standard deviations would to
+/// a reader of a Gaussian. It is held to at least [`FINEST_RESOLUTION`] of the
+/// median: repeats that mostly agree exactly have no spread to measure by, and
+/// would otherwise cast out a run for a difference no timing resolves.
+///
+/// Fewer than three values are all kept, since of two neither can be told to
+/// be the odd one out.
+fn inliers(values: &[f64], deviations: f64) -> Vec<f64> {
+    if values.len() < 3 {
+        return values.to_vec();
+    }
+    let Some(median) = linalg::median(&mut values.to_vec()) else {
+        return values.to_vec();
+    };
+    let residuals: Vec<f64> = values.iter().map(|v| v - median).collect();
+    let scale = robust::spread(&residuals).max(FINEST_RESOLUTION * median.abs());
+    values
+        .iter()
+        .copied()
+        .filter(|v| (v - median).abs() <= deviations * scale)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn prepare(data: &[(f64, f64)]) -> Sample {
+        super::prepare(data, Aggregation::Median, None)
+    }
+
     fn points(data: &[(f64, f64)]) -> Vec<(f64, f64)> {
         prepare(data).points().to_vec()
     }
@@ -145,6 +348,145 @@ mod tests {
         assert_eq!(points(&[(1., 10.), (1., 20.)]), vec![(1., 15.)]);
     }
 
+    #[test]
+    fn collapses_by_the_aggregation_asked_for() {
+        let data = [(1., 10.), (1., 12.), (1., 900.), (2., 20.)];
+
+        let fastest = super::prepare(&data, Aggregation::Min, None);
+        let mean = super::prepare(&data, Aggregation::Mean, None);
+
+        assert_eq!(fastest.points(), &[(1., 10.), (2., 20.)]);
+        assert_eq!(mean.points(), &[(1., 307.3333333333333), (2., 20.)]);
+    }
+
+    #[test]
+    fn keeps_the_spread_of_the_repeats() {
+        let sample = prepare(&[(1., 10.), (1., 12.), (1., 14.), (2., 20.)]);
+
+        assert_eq!(
+            sample.spread(),
+            &[
+                Spread {
+                    repeats: 3,
+                    variance: 4.0,
+                    error: std::f64::consts::FRAC_PI_2 * 4.0 / 3.0,
+                },
+                Spread {
+                    repeats: 1,
+                    variance: 0.0,
+                    error: 0.0,
+                },
+            ]
+        );
+    }
+
+    fn repeated(sizes: &[f64], repeats: usize, noise: impl Fn(f64, usize) -> f64) -> Sample {
+        let data: Vec<(f64, f64)> = sizes
+            .iter()
+            .flat_map(|&n| (0..repeats).map(move |_| (n, n)))
+            .enumerate()
+            .map(|(k, (n, y))| (n, y + noise(n, k)))
+            .collect();
+        prepare(&data)
+    }
+
+    #[test]
+    fn variances_need_enough_repeats_at_every_size() {
+        let noise = |n: f64, k: usize| 0.1 * n * (k as f64 * 1.3).sin();
+
+        assert!(repeated(&[10., 20., 30.], MIN_REPEATS, noise)
+            .variances(1e-3)
+            .is_some());
+        assert_eq!(
+            repeated(&[10., 20., 30.], MIN_REPEATS - 1, noise).variances(1e-3),
+            None
+        );
+    }
+
+    #[test]
+    fn identical_repeats_say_nothing_about_the_noise() {
+        assert_eq!(
+            repeated(&[10., 20., 30.], MIN_REPEATS, |_, _| 0.0).variances(1e-3),
+            None
+        );
+    }
+
+    #[test]
+    fn a_variance_is_no_finer_than_the_resolution_of_its_cost() {
+        let sample = repeated(&[10., 1e6], MIN_REPEATS, |n, k| match n < 100.0 {
+            true => (k % 2) as f64,
+            false => 0.0,
+        });
+        let variances = sample.variances(1e-3).unwrap();
+
+        assert!(variances[0] > 0.01);
+        assert_eq!(variances[1], (FINEST_RESOLUTION * 1e6).powi(2));
+    }
+
+    #[test]
+    fn a_size_that_costs_nothing_is_held_to_the_floor() {
+        let sample = repeated(&[10., 20., 40.], MIN_REPEATS, |n, k| match n < 15.0 {
+            true => -n,
+            false => (k % 3) as f64,
+        });
+        let variances = sample.variances(0.5).unwrap();
+
+        assert_eq!(sample.points()[0], (10., 0.));
+        assert_eq!(variances[0], (FINEST_RESOLUTION * 0.5).powi(2));
+        assert_eq!(sample.variances(0.0), None);
+    }
+
+    #[test]
+    fn rejects_a_repeat_far_from_the_rest_of_its_size() {
+        let data = [
+            (1., 10.),
+            (1., 11.),
+            (1., 10.5),
+            (1., 95.),
+            (1., 9.5),
+            (2., 20.),
+            (2., 21.),
+        ];
+
+        let sample = super::prepare(&data, Aggregation::Mean, Some(3.0));
+
+        assert_eq!(sample.points(), &[(1., 10.25), (2., 20.5)]);
+        assert_eq!(sample.removed(), &[1.0]);
+        assert_eq!(sample.spread()[0].repeats, 4);
+    }
+
+    #[test]
+    fn a_difference_finer_than_any_timing_is_not_an_outlier() {
+        let ticks = [1e6, 1e6, 1e6 + 1.0, 1e6, 1e6, 1e6, 1e6 - 1.0];
+
+        assert_eq!(inliers(&ticks, 3.0), ticks.to_vec());
+        assert_eq!(inliers(&[1.0, 1e6], 3.0), vec![1.0, 1e6]);
+    }
+
+    #[test]
+    fn a_size_set_aside_counts_every_repeat_behind_it() {
+        let sample = super::prepare(
+            &[(1., 10.), (2., 20.), (2., 21.), (2., 90.), (3., 30.)],
+            Aggregation::Median,
+            Some(3.0),
+        );
+
+        let rest = sample.without(&[1]);
+
+        assert_eq!(rest.points(), &[(1., 10.), (3., 30.)]);
+        assert_eq!(rest.removed(), &[2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn a_selection_keeps_each_point_once_with_its_spread() {
+        let sample = prepare(&[(1., 10.), (1., 12.), (2., 20.), (3., 30.)]);
+
+        let selected = sample.select([2, 0, 2, 7]);
+
+        assert_eq!(selected.points(), &[(1., 11.), (3., 30.)]);
+        assert_eq!(selected.spread()[0], sample.spread()[0]);
+    }
+
     #[test]
     fn drops_non_finite_measurements() {
         let sample = prepare(&[(1., 10.), (2., f64::NAN), (f64::INFINITY, 30.), (3., 30.)]);
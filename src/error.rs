@@ -20,6 +20,14 @@ pub enum Error {
     ParseNotation,
     /// Returned when no complexity model fits the input data.
     NoValidComplexity,
+    /// Returned when the kernel would not open a performance counter. `errno`
+    /// is why: `ENOENT` or `EOPNOTSUPP` where there is no such counter,
+    /// `EACCES` or `EPERM` where `perf_event_paranoid` forbids it, `ENOSYS`
+    /// where the system call is filtered out.
+    CounterUnavailable {
+        /// The OS error code the system call failed with.
+        errno: i32,
+    },
 }
 
 impl fmt::Display for Error {
@@ -31,6 +39,11 @@ impl fmt::Display for Error {
             ),
             Error::ParseNotation => write!(f, "Can't convert string to a complexity model"),
             Error::NoValidComplexity => write!(f, "No valid complexity could be inferred"),
+            Error::CounterUnavailable { errno } => write!(
+                f,
+                "No performance counter could be opened: {}",
+                std::io::Error::from_raw_os_error(*errno)
+            ),
         }
     }
 }
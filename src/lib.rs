@@ -1,22 +1,71 @@
 #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
 #![warn(missing_docs)]
 
+mod adaptive;
+mod aggregation;
+#[cfg(feature = "alloc")]
+mod alloc;
 mod analysis;
+mod assert;
+mod compare;
+mod counter;
+mod crossover;
 mod data;
 mod error;
 mod fit;
 mod linalg;
+mod measure;
+mod measurement;
 mod model;
+mod multi;
+#[cfg(all(feature = "perf", target_os = "linux"))]
+mod perf;
+mod prediction;
+mod regime;
+mod robust;
+mod selection;
+mod shape;
+mod uncertainty;
+mod validation;
 mod warning;
 
-pub use crate::analysis::{Analysis, Inference};
+pub use crate::adaptive::Adaptive;
+pub use crate::aggregation::Aggregation;
+#[cfg(feature = "alloc")]
+pub use crate::alloc::{Allocations, CountingAllocator, SpaceInference};
+pub use crate::analysis::{Analysis, Inference, Vote};
+pub use crate::compare::{Comparison, Verdict};
+pub use crate::counter::Counter;
+pub use crate::crossover::{Cheaper, Crossing, Crossover, Segment};
 pub use crate::error::Error;
 pub use crate::fit::{Fit, ModelParams};
+pub use crate::measure::Harness;
+pub use crate::measurement::{Measurement, Quantity};
 pub use crate::model::Model;
+pub use crate::multi::{MultiAnalysis, MultiFit, MultiInference, Term};
+#[cfg(all(feature = "perf", target_os = "linux"))]
+pub use crate::perf::{Event, PerfCounter};
+pub use crate::prediction::Prediction;
+pub use crate::regime::Regime;
+pub use crate::robust::{Influence, Loss};
+pub use crate::selection::{Criterion, Selection};
+pub use crate::shape::Shape;
+pub use crate::uncertainty::Uncertainty;
+pub use crate::validation::Validation;
 pub use crate::warning::Warning;
 
+/// What the exported macros expand to. Not public API: it may change in any
+/// release without notice.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::assert::{assert_complexity, Bound, Measurements};
+}
+
 /// Infers the asymptotic complexity of measured `(input size, cost)` pairs.
 ///
+/// The pairs may be of any [`Quantity`] — `(usize, Duration)`, `(u64, u64)` —
+/// as well as `(f64, f64)`; see [`Measurement`].
+///
 /// Repeated measurements of the same input size are collapsed to their median
 /// before fitting, so an occasional descheduled run does not steer the result.
 /// Non-finite measurements are dropped. A model that cannot describe what
@@ -51,6 +100,8 @@ pub use crate::warning::Warning;
 /// assert!(inference.best.is_at_most(big_o::Model::Quadratic));
 /// assert!(inference.confidence > 0.9);
 /// ```
-pub fn infer_complexity(data: &[(f64, f64)]) -> Result<Inference, Error> {
+pub fn infer_complexity<M: Measurement>(
+    data: impl IntoIterator<Item = M>,
+) -> Result<Inference, Error> {
     Analysis::new().infer(data)
 }
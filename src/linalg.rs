@@ -5,6 +5,11 @@
 //! matrix decomposition. That matters here beyond speed: a decomposition can
 //! fail at runtime on degenerate input, whereas the closed form has exactly one
 //! degenerate case — no spread in `x` — which is reported rather than raised.
+//!
+//! Fits over several input sizes at once need several predictors, and there the
+//! normal equations are solved directly. They stay small — one row per term of
+//! the candidate form — and their degenerate case, predictors that move
+//! together, is again reported as `None` rather than raised.
 
 /// Coefficients of the line `f(x) = gain * x + offset`.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -74,6 +79,147 @@ pub(crate) fn fit_line(data: &[(f64, f64, f64)]) -> Option<Line> {
     Some(Line { gain, offset })
 }
 
+/// Coefficients of the plane `f(x) = gains · x + offset`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Plane {
+    /// Slope along each predictor, in the order the predictors were given.
+    pub gains: Vec<f64>,
+
+    /// Value of the fitted plane where every predictor is zero.
+    pub offset: f64,
+}
+
+/// Smallest pivot, in the normalized normal equations, that is still divided
+/// by.
+///
+/// The equations are scaled so that every diagonal entry is one, which makes a
+/// pivot the share of a predictor's spread that the predictors before it do not
+/// already explain. Below this share the predictor is, to working precision, a
+/// combination of the others and its gain is not determined by the data.
+const SMALLEST_PIVOT: f64 = 1e-10;
+
+/// Fits `f(x) = gains · x + offset` to weighted `(x, y, weight)` points by
+/// least squares, where every `x` has the same number of predictors.
+///
+/// [`fit_line`] is the one-predictor case, and agrees with this on it.
+///
+/// Returns `None` when the coefficients are not determined by the sample:
+/// fewer points than coefficients, predictors of differing lengths, a
+/// non-finite value, no weighted spread in some predictor, or predictors that
+/// are linear combinations of each other.
+pub(crate) fn fit_plane(data: &[(Vec<f64>, f64, f64)]) -> Option<Plane> {
+    let width = data.first()?.0.len();
+    if width == 0 || data.len() < width + 1 {
+        return None;
+    }
+
+    let mut total = 0.0;
+    let mut sum_x = vec![0.0; width];
+    let mut sum_y = 0.0;
+    for (x, y, w) in data {
+        let (y, w) = (*y, *w);
+        if x.len() != width
+            || x.iter().any(|value| !value.is_finite())
+            || !y.is_finite()
+            || !w.is_finite()
+            || w < 0.0
+        {
+            return None;
+        }
+        total += w;
+        for (sum, value) in sum_x.iter_mut().zip(x) {
+            *sum += w * value;
+        }
+        sum_y += w * y;
+    }
+    if !total.is_finite() || total <= 0.0 {
+        return None;
+    }
+    let mean_x: Vec<f64> = sum_x.iter().map(|sum| sum / total).collect();
+    let mean_y = sum_y / total;
+    if mean_x.iter().any(|mean| !mean.is_finite()) || !mean_y.is_finite() {
+        return None;
+    }
+
+    // Centered, as in `fit_line`, and then scaled so each predictor has unit
+    // weighted spread: predictors measured in `n^3` and in `log n` differ by
+    // many orders of magnitude, and unscaled they would make every pivot look
+    // negligible or none of them.
+    let mut moments = vec![vec![0.0; width]; width];
+    let mut cross = vec![0.0; width];
+    for (x, y, w) in data {
+        let dy = y - mean_y;
+        for j in 0..width {
+            let dj = x[j] - mean_x[j];
+            cross[j] += w * dj * dy;
+            for k in j..width {
+                moments[j][k] += w * dj * (x[k] - mean_x[k]);
+            }
+        }
+    }
+    let scale: Vec<f64> = (0..width).map(|j| moments[j][j].sqrt()).collect();
+    if scale.iter().any(|s| !s.is_finite() || *s <= 0.0) {
+        return None;
+    }
+    for j in 0..width {
+        cross[j] /= scale[j];
+        for k in j..width {
+            moments[j][k] /= scale[j] * scale[k];
+            moments[k][j] = moments[j][k];
+        }
+    }
+
+    let solution = solve(moments, cross)?;
+    let gains: Vec<f64> = solution.iter().zip(&scale).map(|(z, s)| z / s).collect();
+    let offset = mean_y
+        - gains
+            .iter()
+            .zip(&mean_x)
+            .map(|(gain, mean)| gain * mean)
+            .sum::<f64>();
+    if gains.iter().any(|gain| !gain.is_finite()) || !offset.is_finite() {
+        return None;
+    }
+    Some(Plane { gains, offset })
+}
+
+/// Solves the square system `a · z = b` by Gaussian elimination with partial
+/// pivoting, or returns `None` if `a` is singular to working precision.
+pub(crate) fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&i, &j| {
+            a[i][column]
+                .abs()
+                .partial_cmp(&a[j][column].abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        // Written so that a NaN pivot is refused along with a small one.
+        if a[pivot][column].abs().partial_cmp(&SMALLEST_PIVOT) != Some(std::cmp::Ordering::Greater)
+        {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        let (done, rest) = a.split_at_mut(column + 1);
+        let pivot_row = &done[column];
+        for (offset, row) in rest.iter_mut().enumerate() {
+            let factor = row[column] / pivot_row[column];
+            for (entry, above) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *entry -= factor * above;
+            }
+            b[column + 1 + offset] -= factor * b[column];
+        }
+    }
+
+    let mut z = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| a[row][k] * z[k]).sum();
+        z[row] = (b[row] - known) / a[row][row];
+    }
+    z.iter().all(|value| value.is_finite()).then_some(z)
+}
+
 /// Returns the weighted mean of `(value, weight)` pairs, or `None` if there is
 /// no weight to average over.
 pub(crate) fn weighted_mean(values: impl IntoIterator<Item = (f64, f64)>) -> Option<f64> {
@@ -129,6 +275,34 @@ pub(crate) fn median(values: &mut [f64]) -> Option<f64> {
     median.is_finite().then_some(median)
 }
 
+/// Returns the `q`-quantile of `values`, for `q` in `[0, 1]`, interpolating
+/// linearly between the two nearest ranks. Reorders the slice.
+///
+/// Returns `None` for an empty slice, under the same assumption about
+/// non-finite values as [`median`].
+pub(crate) fn quantile(values: &mut [f64], q: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = q.clamp(0.0, 1.0) * (values.len() - 1) as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    let quantile = values[below] + (values[above] - values[below]) * (rank - below as f64);
+    quantile.is_finite().then_some(quantile)
+}
+
+/// Returns the sample standard deviation of `values`, or `None` for fewer than
+/// two of them.
+pub(crate) fn standard_deviation(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values.iter().copied())?;
+    let squares: f64 = values.iter().map(|v| (v - mean) * (v - mean)).sum();
+    let deviation = (squares / (values.len() - 1) as f64).sqrt();
+    deviation.is_finite().then_some(deviation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,6 +391,53 @@ mod tests {
         assert_approx_eq!(ignored.offset, 0., 1e-3);
     }
 
+    #[test]
+    fn a_plane_over_one_predictor_is_the_line() {
+        let data = [(1., 3.), (2., 5.5), (4., 9.), (8., 17.5)];
+        let line = fitted(&data);
+
+        let plane = fit_plane(&data.map(|(x, y)| (vec![x], y, 1.0))).expect("determined");
+
+        assert_approx_eq!(plane.gains[0], line.gain, EPSILON);
+        assert_approx_eq!(plane.offset, line.offset, EPSILON);
+    }
+
+    #[test]
+    fn fits_a_plane_over_predictors_of_very_different_scale() {
+        let data: Vec<(Vec<f64>, f64, f64)> = [1., 10., 100., 1000.]
+            .iter()
+            .flat_map(|&a: &f64| [1f64, 2., 3., 5.].map(|b| (a, b)))
+            .map(|(a, b)| {
+                (
+                    vec![a * a * a, b.ln()],
+                    2.0 * a * a * a + 7.0 * b.ln() + 4.0,
+                    1.0,
+                )
+            })
+            .collect();
+
+        let plane = fit_plane(&data).expect("determined");
+
+        assert_approx_eq!(plane.gains[0], 2., 1e-9);
+        assert_approx_eq!(plane.gains[1], 7., 1e-6);
+        assert_approx_eq!(plane.offset, 4., 1e-6);
+    }
+
+    #[test]
+    fn reports_predictors_that_move_together() {
+        let collinear: Vec<(Vec<f64>, f64, f64)> = (1..10)
+            .map(|n| {
+                let n = n as f64;
+                (vec![n, 2.0 * n + 1.0], n, 1.0)
+            })
+            .collect();
+        assert_eq!(fit_plane(&collinear), None);
+
+        let short = [(vec![1., 2.], 1., 1.), (vec![2., 1.], 2., 1.)];
+        assert_eq!(fit_plane(&short), None, "fewer points than coefficients");
+        assert_eq!(fit_plane(&[]), None);
+    }
+
     #[test]
     fn weighted_mean_of_values() {
         assert_eq!(weighted_mean([(1., 1.), (3., 1.)]), Some(2.));
@@ -239,4 +460,25 @@ mod tests {
         assert_eq!(median(&mut [7.]), Some(7.));
         assert_eq!(median(&mut []), None);
     }
+
+    #[test]
+    fn interpolates_quantiles_between_ranks() {
+        let mut values = [4., 1., 3., 2., 5.];
+
+        assert_approx_eq!(quantile(&mut values, 0.0).unwrap(), 1., EPSILON);
+        assert_approx_eq!(quantile(&mut values, 0.5).unwrap(), 3., EPSILON);
+        assert_approx_eq!(quantile(&mut values, 0.125).unwrap(), 1.5, EPSILON);
+        assert_approx_eq!(quantile(&mut values, 1.0).unwrap(), 5., EPSILON);
+        assert_eq!(quantile(&mut [], 0.5), None);
+    }
+
+    #[test]
+    fn standard_deviation_uses_the_sample_denominator() {
+        assert_approx_eq!(
+            standard_deviation(&[2., 4., 4., 4., 5., 5., 7., 9.]).unwrap(),
+            (32f64 / 7.).sqrt(),
+            EPSILON
+        );
+        assert_eq!(standard_deviation(&[1.]), None);
+    }
 }
@@ -0,0 +1,474 @@
+//! `big_o`: infers the complexity of `(size, cost)` measurements from a file or
+//! from standard input.
+//!
+//! The library's audience writes Rust; this is for everyone whose measurements
+//! live in shell scripts and spreadsheets, and who should not have to write a
+//! program around each dataset to ask what it grows like.
+
+use big_o::{Analysis, Inference, Model};
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Infers asymptotic computational complexity from (size, cost) measurements.
+
+Usage: big_o [OPTIONS] [FILE]
+
+Reads FILE, or standard input when FILE is absent or `-`.
+
+Options:
+  -f, --format <FORMAT>        Input format: csv, tsv or json. By default taken
+                               from the file extension, else from the content.
+  -m, --models <MODELS>        Comma-separated models to consider, by name or
+                               notation: `linear,quadratic` or `O(n),O(n^2)`.
+  -a, --accept-range <N,D>     Accept N distinct sizes over D decades as enough,
+                               silencing the range warnings below that.
+  -o, --output <OUTPUT>        Output: text (default) or json.
+  -h, --help                   Print this help.
+  -V, --version                Print the version.
+
+CSV and TSV take the first two columns of each line as size and cost; a first
+line of two columns, neither numeric, is read as a header, and lines starting
+with `#` are skipped. Any other line that does not parse is an error. JSON
+takes an array of `[size, cost]` pairs or of objects with `size` and `cost`
+fields.";
+
+/// Exit status when the measurements were read but nothing could be inferred.
+const NO_INFERENCE: u8 = 1;
+
+/// Exit status when the command line or the input could not be read.
+const BAD_INPUT: u8 = 2;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("big_o {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("big_o: {e}\n\nRun `big_o --help` for usage.");
+            return ExitCode::from(BAD_INPUT);
+        }
+    };
+
+    let data = match options.read() {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("big_o: {e}");
+            return ExitCode::from(BAD_INPUT);
+        }
+    };
+
+    match options.analysis().infer(&data) {
+        Ok(inference) => match options.output.render(&inference) {
+            Ok(text) => {
+                println!("{text}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("big_o: {e}");
+                ExitCode::from(NO_INFERENCE)
+            }
+        },
+        Err(e) => {
+            eprintln!("big_o: {e}");
+            ExitCode::from(NO_INFERENCE)
+        }
+    }
+}
+
+/// What the command line asks for.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(Options),
+    Help,
+    Version,
+}
+
+/// How to read the measurements, analyse them, and write the result.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    path: Option<String>,
+    format: Option<Format>,
+    models: Option<Vec<Model>>,
+    accept_range: Option<(usize, f64)>,
+    output: Output,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Csv,
+    Tsv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Output {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Why the command line or the input could not be read.
+#[derive(Debug, PartialEq)]
+enum CliError {
+    Usage(String),
+    Io(String),
+    Parse { line: usize, text: String },
+    Json(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{message}"),
+            CliError::Io(message) => write!(f, "{message}"),
+            CliError::Parse { line, text } => {
+                write!(f, "line {line}: expected a size and a cost, got `{text}`")
+            }
+            CliError::Json(message) => write!(f, "invalid JSON input: {message}"),
+        }
+    }
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Command, CliError> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| {
+                args.next()
+                    .ok_or_else(|| CliError::Usage(format!("{flag} needs a value")))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-f" | "--format" => options.format = Some(parse_format(value(arg)?)?),
+                "-m" | "--models" => options.models = Some(parse_models(value(arg)?)?),
+                "-a" | "--accept-range" => {
+                    options.accept_range = Some(parse_accept_range(value(arg)?)?)
+                }
+                "-o" | "--output" => options.output = parse_output(value(arg)?)?,
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(CliError::Usage(format!("unknown option `{flag}`")))
+                }
+                path => match options.path {
+                    None => options.path = Some(path.to_string()),
+                    Some(_) => {
+                        return Err(CliError::Usage(format!(
+                            "unexpected argument `{path}`: one input at a time"
+                        )))
+                    }
+                },
+            }
+        }
+        Ok(Command::Run(options))
+    }
+
+    /// The analysis the options describe.
+    fn analysis(&self) -> Analysis {
+        let mut analysis = Analysis::new();
+        if let Some(models) = &self.models {
+            analysis = analysis.models(models.iter().copied());
+        }
+        if let Some((sizes, decades)) = self.accept_range {
+            analysis = analysis.accept_range(sizes, decades);
+        }
+        analysis
+    }
+
+    /// Reads the measurements from the file or from standard input.
+    fn read(&self) -> Result<Vec<(f64, f64)>, CliError> {
+        let text = match self.path.as_deref() {
+            None | Some("-") => {
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| CliError::Io(format!("reading standard input: {e}")))?;
+                text
+            }
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| CliError::Io(format!("reading {path}: {e}")))?,
+        };
+
+        let format = self
+            .format
+            .or_else(|| self.path.as_deref().and_then(format_of_path))
+            .unwrap_or_else(|| sniff(&text));
+        match format {
+            Format::Csv => parse_delimited(&text, ','),
+            Format::Tsv => parse_delimited(&text, '\t'),
+            Format::Json => parse_json(&text),
+        }
+    }
+}
+
+impl Output {
+    fn render(self, inference: &Inference) -> Result<String, CliError> {
+        match self {
+            Output::Text => Ok(inference.to_string()),
+            Output::Json => {
+                serde_json::to_string_pretty(inference).map_err(|e| CliError::Json(e.to_string()))
+            }
+        }
+    }
+}
+
+fn parse_format(value: &str) -> Result<Format, CliError> {
+    match value.to_lowercase().as_str() {
+        "csv" => Ok(Format::Csv),
+        "tsv" => Ok(Format::Tsv),
+        "json" => Ok(Format::Json),
+        other => Err(CliError::Usage(format!(
+            "unknown format `{other}`, expected csv, tsv or json"
+        ))),
+    }
+}
+
+fn parse_output(value: &str) -> Result<Output, CliError> {
+    match value.to_lowercase().as_str() {
+        "text" => Ok(Output::Text),
+        "json" => Ok(Output::Json),
+        other => Err(CliError::Usage(format!(
+            "unknown output `{other}`, expected text or json"
+        ))),
+    }
+}
+
+fn parse_models(value: &str) -> Result<Vec<Model>, CliError> {
+    value
+        .split(',')
+        .map(|name| {
+            name.trim()
+                .parse::<Model>()
+                .map_err(|_| CliError::Usage(format!("unknown model `{}`", name.trim())))
+        })
+        .collect()
+}
+
+fn parse_accept_range(value: &str) -> Result<(usize, f64), CliError> {
+    let invalid = || {
+        CliError::Usage(format!(
+            "--accept-range takes SIZES,DECADES such as `4,1.5`, got `{value}`"
+        ))
+    };
+    let (sizes, decades) = value.split_once(',').ok_or_else(invalid)?;
+    let sizes = sizes.trim().parse().map_err(|_| invalid())?;
+    let decades: f64 = decades.trim().parse().map_err(|_| invalid())?;
+    match decades.is_finite() {
+        true => Ok((sizes, decades)),
+        false => Err(invalid()),
+    }
+}
+
+/// The format a file's extension names, if it names one.
+fn format_of_path(path: &str) -> Option<Format> {
+    let extension = Path::new(path).extension()?.to_str()?;
+    parse_format(extension).ok()
+}
+
+/// Guesses the format of input that arrived without a name: JSON starts with a
+/// bracket or a brace, and a tab on the first data line means TSV.
+fn sniff(text: &str) -> Format {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        return Format::Json;
+    }
+    match trimmed.lines().next() {
+        Some(line) if line.contains('\t') => Format::Tsv,
+        _ => Format::Csv,
+    }
+}
+
+/// Reads size and cost from the first two fields of each line.
+///
+/// A first line of two fields, neither of them a number, is taken for a
+/// header; any other line that does not parse is an error, because silently
+/// skipping a malformed measurement changes the answer without saying so. A
+/// first line such as `10,1.5e`, or `10;2` read as CSV, is a measurement gone
+/// wrong, not a header.
+fn parse_delimited(text: &str, delimiter: char) -> Result<Vec<(f64, f64)>, CliError> {
+    let mut data = Vec::new();
+    let mut seen_first = false;
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut fields = trimmed.split(delimiter).map(str::trim);
+        let (size, cost) = (fields.next(), fields.next());
+        let number = |field: Option<&str>| field.and_then(|field| field.parse::<f64>().ok());
+        let header = !seen_first && cost.is_some() && number(size).or(number(cost)).is_none();
+        match number(size).zip(number(cost)) {
+            Some(point) => data.push(point),
+            None if header => {}
+            None => {
+                return Err(CliError::Parse {
+                    line: index + 1,
+                    text: trimmed.to_string(),
+                })
+            }
+        }
+        seen_first = true;
+    }
+    Ok(data)
+}
+
+/// Reads an array of `[size, cost]` pairs or of `{"size": .., "cost": ..}`
+/// objects.
+fn parse_json(text: &str) -> Result<Vec<(f64, f64)>, CliError> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| CliError::Json(e.to_string()))?;
+    let items = value
+        .as_array()
+        .ok_or_else(|| CliError::Json("expected an array of measurements".to_string()))?;
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let (size, cost) = match item {
+                serde_json::Value::Array(pair) if pair.len() == 2 => (&pair[0], &pair[1]),
+                serde_json::Value::Object(fields) => match (fields.get("size"), fields.get("cost"))
+                {
+                    (Some(size), Some(cost)) => (size, cost),
+                    _ => return Err(malformed(i)),
+                },
+                _ => return Err(malformed(i)),
+            };
+            size.as_f64().zip(cost.as_f64()).ok_or_else(|| malformed(i))
+        })
+        .collect()
+}
+
+fn malformed(index: usize) -> CliError {
+    CliError::Json(format!(
+        "measurement {index} is not a [size, cost] pair or a {{\"size\", \"cost\"}} object"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Command, CliError> {
+        Options::parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let command = run(&[
+            "-f",
+            "tsv",
+            "--models",
+            "linear, O(n^2)",
+            "--accept-range",
+            "4,1.5",
+            "-o",
+            "json",
+            "data.txt",
+        ]);
+
+        assert_eq!(
+            command,
+            Ok(Command::Run(Options {
+                path: Some("data.txt".to_string()),
+                format: Some(Format::Tsv),
+                models: Some(vec![Model::Linear, Model::Quadratic]),
+                accept_range: Some((4, 1.5)),
+                output: Output::Json,
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_what_it_does_not_understand() {
+        assert!(matches!(run(&["--frobnicate"]), Err(CliError::Usage(_))));
+        assert!(matches!(run(&["-m", "quartic"]), Err(CliError::Usage(_))));
+        assert!(matches!(run(&["-a", "4"]), Err(CliError::Usage(_))));
+        assert!(matches!(run(&["-f"]), Err(CliError::Usage(_))));
+        assert!(matches!(run(&["a.csv", "b.csv"]), Err(CliError::Usage(_))));
+        assert_eq!(
+            run(&["-"]),
+            Ok(Command::Run(Options {
+                path: Some("-".to_string()),
+                ..Options::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn reads_delimited_text_with_or_without_a_header() {
+        let expected = vec![(1., 10.), (2., 20.)];
+
+        assert_eq!(parse_delimited("1,10\n2,20\n", ','), Ok(expected.clone()));
+        assert_eq!(
+            parse_delimited("size,cost\n# a comment\n1, 10\n\n2,20,extra\n", ','),
+            Ok(expected.clone())
+        );
+        assert_eq!(parse_delimited("n\tns\n1\t10\n2\t20", '\t'), Ok(expected));
+    }
+
+    #[test]
+    fn a_malformed_line_after_the_first_is_an_error() {
+        assert_eq!(
+            parse_delimited("1,10\n2,oops\n", ','),
+            Err(CliError::Parse {
+                line: 2,
+                text: "2,oops".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn a_malformed_first_row_is_an_error_rather_than_a_header() {
+        for (text, first) in [("10,1.5e\n20,3\n", "10,1.5e"), ("10;2\n20;4\n", "10;2")] {
+            assert_eq!(
+                parse_delimited(text, ','),
+                Err(CliError::Parse {
+                    line: 1,
+                    text: first.to_string()
+                })
+            );
+        }
+        assert_eq!(
+            parse_delimited("# sizes\nsize\n1,10\n", ','),
+            Err(CliError::Parse {
+                line: 2,
+                text: "size".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn reads_json_pairs_or_objects() {
+        assert_eq!(
+            parse_json("[[1, 10], [2, 20.5]]"),
+            Ok(vec![(1., 10.), (2., 20.5)])
+        );
+        assert_eq!(
+            parse_json(r#"[{"size": 1, "cost": 10}, {"cost": 20, "size": 2}]"#),
+            Ok(vec![(1., 10.), (2., 20.)])
+        );
+        assert!(parse_json("[[1, 2, 3]]").is_err());
+        assert!(parse_json(r#"{"size": 1}"#).is_err());
+        assert!(parse_json("not json").is_err());
+    }
+
+    #[test]
+    fn guesses_the_format_from_the_name_or_the_content() {
+        assert_eq!(format_of_path("runs/sort.TSV"), Some(Format::Tsv));
+        assert_eq!(format_of_path("runs/sort.txt"), None);
+        assert_eq!(sniff("  [[1, 2]]"), Format::Json);
+        assert_eq!(sniff("1\t2\n"), Format::Tsv);
+        assert_eq!(sniff("1,2\n"), Format::Csv);
+    }
+}
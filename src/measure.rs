@@ -0,0 +1,447 @@
+//! Measuring a routine over a ladder of input sizes, so that the measurements
+//! an inference needs do not have to be collected by hand.
+//!
+//! Every hand-written timing loop makes the same three decisions, and each is
+//! easy to get subtly wrong: whether the first run pays for cold caches and lazy
+//! initialisation that later runs do not, whether the optimizer is allowed to
+//! see that the result is unused and delete the work, and whether a run short
+//! enough to be mostly clock resolution is timed on its own. The harness makes
+//! those decisions once.
+
+#[cfg(feature = "alloc")]
+use crate::alloc::{Allocations, CountingAllocator, SpaceInference};
+use crate::analysis::{Analysis, Inference};
+use crate::counter::Counter;
+use crate::error::Error;
+#[cfg(all(feature = "perf", target_os = "linux"))]
+use crate::perf::PerfCounter;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Smallest input size on the default ladder.
+const SMALLEST: usize = 64;
+
+/// Largest input size on the default ladder.
+///
+/// Three decades above [`SMALLEST`], which is the range the analysis advises
+/// before the models can be told apart.
+const LARGEST: usize = 65_536;
+
+/// Rungs on the default ladder: every power of two between the two ends.
+const RUNGS: usize = 11;
+
+/// Timed measurements taken at each input size on the default ladder.
+///
+/// Repeats collapse to their median before fitting, unless the analysis
+/// aggregates them otherwise, so an odd number keeps that median a
+/// measurement rather than an average of two.
+const REPEATS: usize = 5;
+
+/// Untimed runs at each input size before the timed ones.
+const WARM_UP: usize = 1;
+
+/// Shortest interval worth timing on its own.
+///
+/// Below a few microseconds the reading is dominated by the clock's own
+/// resolution and the cost of reading it, so a routine that finishes faster is
+/// run repeatedly inside one timing and the total divided by the count. Set
+/// well above resolution and well below the time a human notices.
+const SHORTEST_TIMING: Duration = Duration::from_micros(100);
+
+/// Measures a routine over a ladder of input sizes and infers its complexity.
+///
+/// For each size on the ladder the harness builds one input, runs the routine
+/// on it untimed to warm up, then times it repeatedly. The input is built
+/// outside the timing, so only the routine's cost is measured.
+///
+/// # Example
+/// ```
+/// use big_o::{Harness, Model};
+///
+/// let inference = Harness::new()
+///     .ladder(1_000, 1_000_000, 7)
+///     .infer(
+///         |n| (0..n as u64).collect::<Vec<_>>(),
+///         |input| input.iter().sum::<u64>(),
+///     )
+///     .unwrap();
+///
+/// assert!(inference.best.is_at_most(Model::Quadratic));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Harness {
+    sizes: Vec<usize>,
+    repeats: usize,
+    warm_up: usize,
+    analysis: Analysis,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// A harness over the powers of two from 64 to 65536, timing each size
+    /// five times after one warm-up run.
+    pub fn new() -> Self {
+        Self {
+            sizes: geometric(SMALLEST, LARGEST, RUNGS),
+            repeats: REPEATS,
+            warm_up: WARM_UP,
+            analysis: Analysis::new(),
+        }
+    }
+
+    /// Measures `rungs` input sizes spaced geometrically from `smallest` to
+    /// `largest`, both included.
+    ///
+    /// Geometric because the models are told apart by how fast cost grows, so
+    /// what matters is how many decades the ladder spans; evenly spaced sizes
+    /// crowd the evidence into the top decade.
+    pub fn ladder(mut self, smallest: usize, largest: usize, rungs: usize) -> Self {
+        self.sizes = geometric(smallest, largest, rungs);
+        self
+    }
+
+    /// Measures exactly these input sizes.
+    pub fn sizes(mut self, sizes: impl IntoIterator<Item = usize>) -> Self {
+        self.sizes = sizes.into_iter().collect();
+        self
+    }
+
+    /// Times each input size this many times.
+    pub fn repeats(mut self, repeats: usize) -> Self {
+        self.repeats = repeats;
+        self
+    }
+
+    /// Runs the routine this many times, untimed, before timing each size.
+    pub fn warm_up(mut self, runs: usize) -> Self {
+        self.warm_up = runs;
+        self
+    }
+
+    /// Infers with this analysis rather than the default one.
+    pub fn analysis(mut self, analysis: Analysis) -> Self {
+        self.analysis = analysis;
+        self
+    }
+
+    /// Times `routine` over the ladder and infers its complexity.
+    ///
+    /// `generate` builds the input for a size and is never timed. Whatever
+    /// `routine` returns is passed through [`black_box`], so work whose result
+    /// is otherwise unused is not optimized away.
+    ///
+    /// # Errors
+    /// As [`Analysis::infer`]: a ladder of fewer than three distinct sizes is
+    /// [`Error::NotEnoughData`].
+    pub fn infer<I, R>(
+        &self,
+        generate: impl FnMut(usize) -> I,
+        routine: impl FnMut(&I) -> R,
+    ) -> Result<Inference, Error> {
+        self.analysis.infer(self.measure(generate, routine))
+    }
+
+    /// Times `routine` over the ladder and returns the raw `(input size,
+    /// seconds)` measurements, one per timed repeat.
+    ///
+    /// For keeping the measurements alongside the inference, or inferring them
+    /// more than one way.
+    pub fn measure<I, R>(
+        &self,
+        generate: impl FnMut(usize) -> I,
+        mut routine: impl FnMut(&I) -> R,
+    ) -> Vec<(f64, f64)> {
+        self.measure_cost(generate, |input| {
+            let (runs, elapsed) = time(|| {
+                black_box(routine(black_box(input)));
+            });
+            elapsed.as_secs_f64() / runs as f64
+        })
+    }
+
+    /// Infers the complexity of a cost other than time.
+    ///
+    /// `cost` runs the routine on the input and reports what that run cost, in
+    /// whatever unit it counts: comparisons made, bytes allocated, instructions
+    /// retired. The harness supplies the ladder, the warm-up and the repeats.
+    ///
+    /// # Errors
+    /// As [`Harness::infer`].
+    pub fn infer_cost<I>(
+        &self,
+        generate: impl FnMut(usize) -> I,
+        cost: impl FnMut(&I) -> f64,
+    ) -> Result<Inference, Error> {
+        self.analysis.infer(self.measure_cost(generate, cost))
+    }
+
+    /// Runs `routine` over the ladder and infers how many operations it counts
+    /// on the [`Counter`] it is handed: comparisons, swaps, node visits.
+    ///
+    /// The counts are exact and the same on every run, so there is no noise
+    /// for the fit to weigh and one repeat per size is as good as many. Only
+    /// what the routine counts on the calling thread is counted; building the
+    /// input is not.
+    ///
+    /// # Errors
+    /// As [`Harness::infer`].
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{Harness, Model};
+    ///
+    /// // Comparisons made by the standard library's sort, on keys in no
+    /// // particular order.
+    /// let scrambled = |n: usize| (0..n as u64).map(|k| k.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    /// let inference = Harness::new()
+    ///     .ladder(64, 65_536, 11)
+    ///     .repeats(1)
+    ///     .infer_operations(
+    ///         |n| scrambled(n).collect::<Vec<_>>(),
+    ///         |input, counter| {
+    ///             let mut sorted = input.clone();
+    ///             sorted.sort_by(|a, b| {
+    ///                 counter.increment();
+    ///                 a.cmp(b)
+    ///             });
+    ///             sorted
+    ///         },
+    ///     )
+    ///     .unwrap();
+    ///
+    /// assert_eq!(inference.best.model, Model::Linearithmic);
+    /// ```
+    pub fn infer_operations<I, R>(
+        &self,
+        generate: impl FnMut(usize) -> I,
+        mut routine: impl FnMut(&I, Counter) -> R,
+    ) -> Result<Inference, Error> {
+        let counter = Counter::new();
+        self.analysis.infer(self.walk(generate, |input| {
+            counter
+                .measure(|| black_box(routine(black_box(input), counter)))
+                .1
+        }))
+    }
+
+    /// Runs `routine` over the ladder under `allocator`, and infers how the
+    /// heap it uses grows: the most it holds at once, the bytes it allocates
+    /// in all, and how many allocations it makes. One run of each repeat is
+    /// counted three ways, and each count inferred on its own.
+    ///
+    /// `allocator` has to be the program's `#[global_allocator]`, or nothing
+    /// is counted. It counts every thread, so measure nothing else at the
+    /// same time. Allocation counts have little noise to speak of, so a repeat
+    /// or two is plenty. Building the input is never counted; whatever
+    /// `routine` returns is, since it is still live when the routine returns.
+    ///
+    /// # Errors
+    /// As [`Harness::infer`], for any of the three.
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{CountingAllocator, Harness, Model};
+    /// use std::alloc::System;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+    ///
+    /// fn main() {
+    ///     // Collects every pair: quadratic space, in one allocation that grows.
+    ///     let space = Harness::new()
+    ///         .ladder(16, 1_024, 7)
+    ///         .repeats(1)
+    ///         .infer_space(
+    ///             &ALLOCATOR,
+    ///             |n| (0..n as u32).collect::<Vec<_>>(),
+    ///             |input| {
+    ///                 let mut pairs = Vec::new();
+    ///                 for &a in input {
+    ///                     for &b in input {
+    ///                         pairs.push((a, b));
+    ///                     }
+    ///                 }
+    ///                 pairs
+    ///             },
+    ///         )
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(space.peak_bytes.best.model, Model::Quadratic);
+    ///     assert!(space.allocations.best.is_faster_than(Model::Linear));
+    /// }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn infer_space<I, R, A>(
+        &self,
+        allocator: &CountingAllocator<A>,
+        generate: impl FnMut(usize) -> I,
+        mut routine: impl FnMut(&I) -> R,
+    ) -> Result<SpaceInference, Error> {
+        let runs = self.walk(generate, |input| {
+            allocator.measure(|| black_box(routine(black_box(input)))).1
+        });
+        let infer = |count: fn(&Allocations) -> usize| {
+            self.analysis.infer(
+                runs.iter()
+                    .map(|(size, allocations)| (*size, count(allocations))),
+            )
+        };
+        Ok(SpaceInference {
+            peak_bytes: infer(|allocations| allocations.peak_bytes)?,
+            total_bytes: infer(|allocations| allocations.total_bytes)?,
+            allocations: infer(|allocations| allocations.count)?,
+        })
+    }
+
+    /// Runs `routine` over the ladder under `counter`, and infers how what it
+    /// counts grows — instructions retired, where the machine exposes them.
+    ///
+    /// Counts are exact integers, inferred as they are: a count past 2^53 is
+    /// reported as [`Warning::LossyConversion`](crate::Warning::LossyConversion)
+    /// like any other. Instructions hardly vary between runs, so a few repeats
+    /// do; a task clock varies as a timing does, and wants as many as one.
+    /// Building the input is never counted.
+    ///
+    /// # Errors
+    /// As [`Harness::infer`].
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    pub fn infer_counted<I, R>(
+        &self,
+        counter: &PerfCounter,
+        generate: impl FnMut(usize) -> I,
+        mut routine: impl FnMut(&I) -> R,
+    ) -> Result<Inference, Error> {
+        self.analysis.infer(self.walk(generate, |input| {
+            counter.measure(|| black_box(routine(black_box(input)))).1
+        }))
+    }
+
+    /// Walks the ladder, reporting `cost` of each repeat at each size.
+    fn measure_cost<I>(
+        &self,
+        generate: impl FnMut(usize) -> I,
+        cost: impl FnMut(&I) -> f64,
+    ) -> Vec<(f64, f64)> {
+        self.walk(generate, cost)
+            .into_iter()
+            .map(|(size, cost)| (size as f64, cost))
+            .collect()
+    }
+
+    /// Walks the ladder, warming up on each size's input and then reporting
+    /// what `run` returns for each repeat.
+    fn walk<I, T>(
+        &self,
+        mut generate: impl FnMut(usize) -> I,
+        mut run: impl FnMut(&I) -> T,
+    ) -> Vec<(usize, T)> {
+        let mut measurements = Vec::with_capacity(self.sizes.len() * self.repeats);
+        for &size in &self.sizes {
+            let input = generate(size);
+            for _ in 0..self.warm_up {
+                black_box(run(&input));
+            }
+            for _ in 0..self.repeats {
+                measurements.push((size, run(&input)));
+            }
+        }
+        measurements
+    }
+}
+
+/// Times `run`, repeating it until the total is long enough to read reliably.
+///
+/// Returns how many times it ran and how long that took in all. The count
+/// doubles between attempts, so a fast routine reaches [`SHORTEST_TIMING`] in a
+/// handful of timings and a slow one is run exactly once.
+fn time(mut run: impl FnMut()) -> (u64, Duration) {
+    let mut runs: u64 = 1;
+    loop {
+        let start = Instant::now();
+        for _ in 0..runs {
+            run();
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= SHORTEST_TIMING || runs >= u64::MAX / 2 {
+            return (runs, elapsed);
+        }
+        runs *= 2;
+    }
+}
+
+/// `rungs` sizes spaced geometrically from `smallest` to `largest`.
+///
+/// Rounding to whole sizes can make neighbouring rungs coincide at the small
+/// end; those are merged rather than measured twice, since repeated sizes
+/// collapse to one point before fitting anyway.
+fn geometric(smallest: usize, largest: usize, rungs: usize) -> Vec<usize> {
+    let (low, high) = (smallest.min(largest).max(1), largest.max(smallest).max(1));
+    let ratio = high as f64 / low as f64;
+    let mut sizes: Vec<usize> = (0..rungs)
+        .map(|i| match rungs {
+            1 => low,
+            _ => (low as f64 * ratio.powf(i as f64 / (rungs - 1) as f64)).round() as usize,
+        })
+        .collect();
+    sizes.dedup();
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_ladder_is_geometric_and_includes_both_ends() {
+        assert_eq!(geometric(64, 65_536, 11), {
+            (6..=16).map(|k| 1usize << k).collect::<Vec<_>>()
+        });
+        assert_eq!(geometric(10, 1000, 3), vec![10, 100, 1000]);
+    }
+
+    #[test]
+    fn coinciding_rungs_are_measured_once() {
+        assert_eq!(geometric(1, 4, 8), vec![1, 2, 3, 4]);
+        assert_eq!(geometric(5, 5, 3), vec![5]);
+        assert_eq!(geometric(7, 7, 1), vec![7]);
+        assert!(geometric(1, 10, 0).is_empty());
+    }
+
+    #[test]
+    fn a_fast_routine_is_repeated_until_it_can_be_timed() {
+        let (runs, elapsed) = time(|| {
+            black_box(0u64);
+        });
+
+        assert!(runs > 1, "a no-op should not be timed on its own");
+        assert!(elapsed >= SHORTEST_TIMING);
+    }
+
+    #[test]
+    fn every_repeat_of_every_rung_is_reported() {
+        let harness = Harness::new().sizes([1, 2, 3]).repeats(4).warm_up(2);
+        let mut generated = Vec::new();
+        let mut calls = 0;
+
+        let measurements = harness.measure_cost(
+            |n| {
+                generated.push(n);
+                n
+            },
+            |&n| {
+                calls += 1;
+                n as f64
+            },
+        );
+
+        assert_eq!(generated, vec![1, 2, 3], "one input per size");
+        assert_eq!(calls, 3 * (4 + 2), "warm-up runs are made but not kept");
+        assert_eq!(measurements.len(), 12);
+        assert!(measurements.iter().all(|&(x, y)| x == y));
+    }
+}
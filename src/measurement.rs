@@ -0,0 +1,204 @@
+//! Measurements in the types benchmarks record them in.
+//!
+//! Fitting happens in `f64`, but few benchmarks measure in it: input sizes are
+//! `usize`, timings are [`Duration`]s, and cycle counters are `u64`. Converting
+//! by hand is a chore, and `as f64` rounds silently once a count passes 2^53 —
+//! a hundred days in nanoseconds, but an afternoon of cycles on a fast core.
+//! Taking the recorded types lets the conversion be checked, and the rounding
+//! reported as a [`Warning`](crate::Warning) rather than lost.
+
+use std::time::Duration;
+
+/// A number a measurement can be recorded in.
+///
+/// Implemented for the primitive integers and floats, and for [`Duration`],
+/// which counts as its whole number of nanoseconds — the unit it stores, so
+/// nothing is lost on the way to a float that the float could have held.
+/// Fitted coefficients are then per nanosecond.
+pub trait Quantity: Copy {
+    /// The quantity as a float, rounded to the nearest one if no float is
+    /// exactly equal to it.
+    fn to_f64(self) -> f64;
+
+    /// Whether [`to_f64`](Self::to_f64) is exactly equal to the quantity.
+    fn is_exact(self) -> bool;
+}
+
+/// One measurement: an input size and what running at it cost.
+///
+/// Implemented for every `(size, cost)` pair of [`Quantity`]s — so
+/// [`Analysis::infer`](crate::Analysis::infer) takes `&[(usize, Duration)]` or
+/// `&[(u64, u64)]` as readily as `&[(f64, f64)]` — and for references to
+/// measurements, so a slice or a `Vec` can be passed by reference. Implement
+/// it for a benchmark's own record type to infer from those directly.
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, Model};
+/// use std::time::Duration;
+///
+/// let data: Vec<(usize, Duration)> = (1..=12)
+///     .map(|k| 1usize << k)
+///     .map(|n| (n, Duration::from_nanos(40 * n as u64 + 900)))
+///     .collect();
+///
+/// let inference = Analysis::new().infer(&data).unwrap();
+///
+/// assert_eq!(inference.best.model, Model::Linear);
+/// assert!((inference.best.evaluate(1_000.0) - 40_900.0).abs() < 1e-3);
+/// ```
+pub trait Measurement {
+    /// What the input size is recorded in.
+    type Size: Quantity;
+
+    /// What the cost is recorded in.
+    type Cost: Quantity;
+
+    /// The input size measured at.
+    fn size(&self) -> Self::Size;
+
+    /// What the measurement cost.
+    fn cost(&self) -> Self::Cost;
+}
+
+impl<S: Quantity, C: Quantity> Measurement for (S, C) {
+    type Size = S;
+    type Cost = C;
+
+    fn size(&self) -> S {
+        self.0
+    }
+
+    fn cost(&self) -> C {
+        self.1
+    }
+}
+
+impl<M: Measurement + ?Sized> Measurement for &M {
+    type Size = M::Size;
+    type Cost = M::Cost;
+
+    fn size(&self) -> M::Size {
+        (**self).size()
+    }
+
+    fn cost(&self) -> M::Cost {
+        (**self).cost()
+    }
+}
+
+/// Quantities every value of which a float holds exactly.
+macro_rules! exact {
+    ($($t:ty),*) => {$(
+        impl Quantity for $t {
+            fn to_f64(self) -> f64 {
+                f64::from(self)
+            }
+
+            fn is_exact(self) -> bool {
+                true
+            }
+        }
+    )*};
+}
+
+/// Integers too wide for a float to hold every value of. One is exact if it
+/// survives the round trip — except the largest values, which round up to a
+/// float one past the type's range and saturate back to where they started.
+macro_rules! rounded {
+    ($($t:ty),*) => {$(
+        impl Quantity for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn is_exact(self) -> bool {
+                let float = self as f64;
+                float as $t == self && float != <$t>::MAX as f64
+            }
+        }
+    )*};
+}
+
+exact!(u8, u16, u32, i8, i16, i32, f32, f64);
+rounded!(u64, u128, usize, i64, i128, isize);
+
+impl Quantity for Duration {
+    fn to_f64(self) -> f64 {
+        self.as_nanos().to_f64()
+    }
+
+    fn is_exact(self) -> bool {
+        self.as_nanos().is_exact()
+    }
+}
+
+/// `data` as the `(size, cost)` floats the analysis fits, and how many of the
+/// measurements had a size or cost that had to be rounded to get there.
+pub(crate) fn collect<M: Measurement>(
+    data: impl IntoIterator<Item = M>,
+) -> (Vec<(f64, f64)>, usize) {
+    let mut rounded = 0;
+    let points = data
+        .into_iter()
+        .map(|measurement| {
+            let (size, cost) = (measurement.size(), measurement.cost());
+            if !size.is_exact() || !cost.is_exact() {
+                rounded += 1;
+            }
+            (size.to_f64(), cost.to_f64())
+        })
+        .collect();
+    (points, rounded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_integers_and_floats_convert_exactly() {
+        assert!(u32::MAX.is_exact());
+        assert!(i32::MIN.is_exact());
+        assert!(f32::MAX.is_exact());
+        assert_eq!(u32::MAX.to_f64(), 4_294_967_295.0);
+    }
+
+    #[test]
+    fn a_wide_integer_is_exact_up_to_two_to_the_fifty_third() {
+        let limit: u64 = 1 << 53;
+
+        assert!(limit.is_exact());
+        assert!(!(limit + 1).is_exact());
+        assert!((limit + 2).is_exact());
+        assert!((1u64 << 60).is_exact());
+        assert!(i64::MIN.is_exact());
+    }
+
+    #[test]
+    fn the_largest_integers_are_not_mistaken_for_exact() {
+        assert!(!u64::MAX.is_exact());
+        assert!(!i64::MAX.is_exact());
+        assert!(!usize::MAX.is_exact());
+        assert!(!u128::MAX.is_exact());
+    }
+
+    #[test]
+    fn a_duration_counts_in_nanoseconds() {
+        let long = Duration::from_nanos((1 << 53) + 1);
+
+        assert_eq!(Duration::from_micros(3).to_f64(), 3_000.0);
+        assert!(Duration::from_secs(86_400).is_exact());
+        assert!(!long.is_exact());
+    }
+
+    #[test]
+    fn collecting_counts_the_measurements_rounded() {
+        let data = [(1u64, 1u64 << 54), (2, (1 << 54) + 1), ((1 << 53) + 1, 3)];
+
+        let (points, rounded) = collect(data);
+
+        assert_eq!(points[0], (1.0, 2f64.powi(54)));
+        assert_eq!(rounded, 2);
+    }
+}
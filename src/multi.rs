@@ -0,0 +1,889 @@
+//! Inferring a complexity in several input sizes at once: `O(V + E)` for a
+//! graph traversal, `O(n*m)` for a table fill, `O(n log m)` for a batch of
+//! searches.
+//!
+//! A single size cannot describe these routines however it is chosen. Fixing
+//! all but one size and sweeping it recovers each variable's own growth, but
+//! not how they combine — a sum and a product look alike along every axis and
+//! differ only across them.
+//!
+//! The candidate forms are built from the single-size models: every product of
+//! one factor per size, and every sum of single-size terms that hold up on
+//! their own. Each is linear in its gains once the factors are evaluated, so
+//! fitting stays a closed-form least-squares solve, and the forms are then
+//! judged by the rules [`Analysis`](crate::Analysis) applies to the single-size
+//! models.
+
+use crate::analysis::{self, Rng, ADVISED_DECADES, ADVISED_POINTS, RESAMPLES, SEED};
+use crate::data;
+use crate::error::Error;
+use crate::fit::{self, SMALLEST_MEANINGFUL_SHARE};
+use crate::linalg;
+use crate::model::Model;
+use crate::warning::Warning;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Factors each size may carry in a candidate form, unless restricted.
+const FACTORS: [Model; 7] = [
+    Model::Logarithmic,
+    Model::CubeRoot,
+    Model::SquareRoot,
+    Model::Linear,
+    Model::Linearithmic,
+    Model::Quadratic,
+    Model::Cubic,
+];
+
+/// Input sizes an analysis may infer over at once.
+///
+/// The products alone number eight to the power of the sizes, and every
+/// resample behind the confidence refits them all: at three sizes that is
+/// already some fifty thousand fits an inference, at four it would be eight
+/// times as many. A routine with more sizes than this is better measured with
+/// some of them held still.
+pub(crate) const MAX_SIZES: usize = 3;
+
+/// Factors each size may carry as a term of a sum: those whose single-size
+/// forms fit that size best.
+///
+/// A term that loses on its own rarely wins inside a sum, and pruning the
+/// rest holds the sums to four to the power of the sizes rather than eight,
+/// about a tenth of the candidates at three sizes.
+const SUM_FACTORS: usize = 3;
+
+/// Correlation between the logarithms of two sizes above which their effects
+/// are reported as inseparable.
+///
+/// Set where a sum of the two and either one alone start fitting within the
+/// selection's tolerance of each other on noisy data, which is when the form
+/// reported stops being a finding about the routine and becomes a finding about
+/// how it was measured.
+const CORRELATION_LIMIT: f64 = 0.95;
+
+/// One term of a [`MultiFit`]: a gain times one factor per input size.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Term {
+    /// How this term grows in each input size, in the order the sizes were
+    /// named. [`Model::Constant`] for a size the term does not depend on.
+    pub factors: Vec<Model>,
+
+    /// Multiplier of the product of the factors.
+    pub gain: f64,
+}
+
+impl Term {
+    /// Evaluates the term at `sizes`.
+    pub fn evaluate(&self, sizes: &[f64]) -> f64 {
+        self.gain * product(&self.factors, sizes).unwrap_or(f64::NAN)
+    }
+}
+
+/// One form fitted to measurements over several input sizes.
+///
+/// `f(sizes) = offset + sum of terms`, each term a gain times a product of
+/// single-size factors. A product form such as `O(n*m)` has one term; a sum
+/// such as `O(V + E)` has one per size it adds; `O(1)` has none.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct MultiFit {
+    /// The terms the cost is the sum of, with the gains recovered from the data.
+    pub terms: Vec<Term>,
+
+    /// Cost left over when every term is zero.
+    pub offset: f64,
+
+    /// Fraction of the variation in cost this fit explains, as
+    /// [`Fit::r_squared`](crate::Fit::r_squared).
+    pub r_squared: f64,
+
+    /// Typical error as a fraction of the measurement, as
+    /// [`Fit::relative_error`](crate::Fit::relative_error).
+    pub relative_error: f64,
+
+    /// Names of the input sizes, in order, as the notation writes them.
+    pub names: Vec<String>,
+}
+
+impl MultiFit {
+    /// Evaluates the fitted function at `sizes`, given in the order the sizes
+    /// were named.
+    pub fn evaluate(&self, sizes: &[f64]) -> f64 {
+        self.offset
+            + self
+                .terms
+                .iter()
+                .map(|term| term.evaluate(sizes))
+                .sum::<f64>()
+    }
+
+    /// Whether `other` is the same form, whatever its gains.
+    fn same_form(&self, other: &MultiFit) -> bool {
+        self.terms.len() == other.terms.len()
+            && self
+                .terms
+                .iter()
+                .zip(&other.terms)
+                .all(|(a, b)| a.factors == b.factors)
+    }
+
+    /// Free parameters the form estimates: a gain per term and the offset.
+    fn parameters(&self) -> usize {
+        self.terms.len() + 1
+    }
+}
+
+/// Renders the form in the sizes' own names: `O(V + E)`, `O(n*m log n)`.
+///
+/// Within a term, the powers come first and are joined by `*`, and the
+/// logarithms follow, so a term reads the way it would be written by hand.
+impl fmt::Display for MultiFit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "{}", Model::Constant.notation());
+        }
+        write!(f, "O(")?;
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{}", notation(&term.factors, &self.names))?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// The notation of one term, without the surrounding `O(...)`.
+fn notation(factors: &[Model], names: &[String]) -> String {
+    let mut powers = Vec::new();
+    let mut logs = Vec::new();
+    for (factor, name) in factors.iter().zip(names) {
+        match factor {
+            Model::CubeRoot => powers.push(format!("{name}^(1/3)")),
+            Model::SquareRoot => powers.push(format!("sqrt {name}")),
+            Model::Linear => powers.push(name.clone()),
+            Model::Linearithmic => {
+                powers.push(name.clone());
+                logs.push(format!("log {name}"));
+            }
+            Model::Quadratic => powers.push(format!("{name}^2")),
+            Model::Cubic => powers.push(format!("{name}^3")),
+            Model::Logarithmic => logs.push(format!("log {name}")),
+            _ => {}
+        }
+    }
+    match (powers.is_empty(), logs.is_empty()) {
+        (_, true) => powers.join("*"),
+        (true, false) => logs.join(" "),
+        (false, false) => format!("{} {}", powers.join("*"), logs.join(" ")),
+    }
+}
+
+/// The outcome of inferring a complexity in several input sizes.
+///
+/// The counterpart of [`Inference`](crate::Inference), read the same way.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct MultiInference {
+    /// The form that best describes the measurements.
+    pub best: MultiFit,
+
+    /// Every form that could be fitted, ordered best first.
+    pub all: Vec<MultiFit>,
+
+    /// Fraction of resampled subsets of the measurements that chose the same
+    /// form, in `[0, 1]`.
+    pub confidence: f64,
+
+    /// Conditions that weaken the inference without invalidating it.
+    pub warnings: Vec<Warning>,
+}
+
+/// The winning form, the runners-up, the confidence and the warnings, one item
+/// per line, laid out as [`Inference`](crate::Inference) writes them.
+impl fmt::Display for MultiInference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        analysis::write_ranking(f, &self.best, &self.all, self.confidence, |fit| {
+            format!(
+                "relative error {:.4}, r^2 {:.4}",
+                fit.relative_error, fit.r_squared
+            )
+        })?;
+        analysis::write_warnings(f, &self.warnings)
+    }
+}
+
+/// Inference over measurements taken at `K` input sizes at once.
+///
+/// Each measurement is the sizes it was taken at and its cost. Repeated
+/// measurements at the same sizes collapse to their median, as in
+/// [`Analysis::infer`](crate::Analysis::infer).
+///
+/// The sizes have to vary independently for their effects to be told apart.
+/// Measure over a grid, or sweep each size with the others held still; sizes
+/// that rise together raise [`Warning::CorrelatedSizes`].
+///
+/// At most three sizes. Every product of one factor per size competes,
+/// as does every sum of single-size terms drawn from the few factors that fit
+/// each size best on their own: at most `8^K` products and `4^K` sums, 576
+/// forms at three sizes.
+///
+/// # Example
+/// ```
+/// use big_o::MultiAnalysis;
+///
+/// // A graph traversal, measured over a grid of vertex and edge counts.
+/// let mut data = Vec::new();
+/// for v in [10., 100., 1_000., 10_000.] {
+///     for e in [20., 200., 2_000., 20_000.] {
+///         data.push(([v, e], 3.0 * v + 5.0 * e + 40.0));
+///     }
+/// }
+///
+/// let inference = MultiAnalysis::new(["V", "E"]).infer(&data).unwrap();
+///
+/// assert_eq!(inference.best.to_string(), "O(V + E)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiAnalysis<const K: usize> {
+    names: Vec<String>,
+    factors: Vec<Model>,
+    advised_points: usize,
+    advised_decades: f64,
+    resamples: usize,
+    seed: u64,
+}
+
+impl<const K: usize> MultiAnalysis<K> {
+    /// An analysis over sizes with these names, which is how the notation
+    /// writes them.
+    ///
+    /// # Panics
+    /// If there are more than three names.
+    pub fn new(names: [&str; K]) -> Self {
+        assert!(
+            K <= MAX_SIZES,
+            "a complexity is inferred in at most {MAX_SIZES} input sizes at once, got {K}"
+        );
+        Self {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            factors: FACTORS.to_vec(),
+            advised_points: ADVISED_POINTS,
+            advised_decades: ADVISED_DECADES,
+            resamples: RESAMPLES,
+            seed: SEED,
+        }
+    }
+
+    /// Restricts the factors a size may carry in a candidate form.
+    ///
+    /// Only models with a fixed shape can be factors: [`Model::Polynomial`]
+    /// and [`Model::Exponential`] are ignored, and [`Model::Constant`] is how
+    /// a term leaves a size out, which every form may already do.
+    pub fn factors(mut self, models: impl IntoIterator<Item = Model>) -> Self {
+        self.factors = models
+            .into_iter()
+            .filter(|model| FACTORS.contains(model))
+            .collect();
+        self
+    }
+
+    /// Declares the sample the caller can afford, as
+    /// [`Analysis::accept_range`](crate::Analysis::accept_range). The range
+    /// compared against `decades` is that of the narrowest size.
+    pub fn accept_range(mut self, sizes: usize, decades: f64) -> Self {
+        self.advised_points = sizes;
+        self.advised_decades = decades;
+        self
+    }
+
+    /// Sets how many resamples [`MultiInference::confidence`] is estimated
+    /// from, as [`Analysis::resamples`](crate::Analysis::resamples). 100 by
+    /// default.
+    ///
+    /// Each refits every candidate form, which at three sizes is hundreds of
+    /// them: fewer resamples are the first thing to trade when an inference is
+    /// slow.
+    pub fn resamples(mut self, resamples: usize) -> Self {
+        self.resamples = resamples;
+        self
+    }
+
+    /// Sets the seed the resamples are drawn from, as
+    /// [`Analysis::seed`](crate::Analysis::seed).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Infers the complexity of `data`.
+    ///
+    /// # Errors
+    /// Returns [`Error::NotEnoughData`] if fewer than three distinct
+    /// combinations of sizes survive preparation, and
+    /// [`Error::NoValidComplexity`] if no candidate form describes what does.
+    pub fn infer(&self, data: &[([f64; K], f64)]) -> Result<MultiInference, Error> {
+        let sample = prepare(data);
+        if sample.len() < data::MIN_POINTS {
+            return Err(Error::NotEnoughData {
+                needed: data::MIN_POINTS,
+                got: sample.len(),
+            });
+        }
+
+        let forms = self.forms(&sample);
+        let all = self.fit_all(&forms, &sample);
+        let best = select(&all, sample.len()).ok_or(Error::NoValidComplexity)?;
+
+        Ok(MultiInference {
+            confidence: self.confidence(&forms, &sample, &best),
+            warnings: self.warnings(&sample),
+            best,
+            all,
+        })
+    }
+
+    /// Every candidate form for `sample`, each as the factors of its terms.
+    ///
+    /// `O(1)`; every product of one factor or [`Model::Constant`] per size,
+    /// short of all constant; and every sum, over two or more sizes, of one
+    /// single-size term each, drawn from the [`SUM_FACTORS`] factors whose
+    /// single-size forms fit that size best. Chosen once from the whole sample,
+    /// so that every resample is offered the same forms.
+    fn forms(&self, sample: &[([f64; K], f64)]) -> Vec<Vec<[Model; K]>> {
+        let mut forms = vec![Vec::new()];
+
+        let choices: Vec<Model> = std::iter::once(Model::Constant)
+            .chain(self.factors.iter().copied())
+            .collect();
+        for product in assignments::<K>(&choices) {
+            if product.iter().any(|&factor| factor != Model::Constant) {
+                forms.push(vec![product]);
+            }
+        }
+
+        let points = sample.len();
+        let shortlists: Vec<Vec<Model>> = (0..K)
+            .map(|i| {
+                let mut alone: Vec<(Model, f64)> = self
+                    .factors
+                    .iter()
+                    .filter_map(|&factor| {
+                        let mut factors = [Model::Constant; K];
+                        factors[i] = factor;
+                        let fit = self.fit(&[factors], sample).filter(is_plausible)?;
+                        Some((factor, corrected_error(&fit, points)))
+                    })
+                    .collect();
+                alone.sort_by(|a, b| cmp(a.1, b.1));
+                alone.truncate(SUM_FACTORS);
+                std::iter::once(Model::Constant)
+                    .chain(alone.into_iter().map(|(factor, _)| factor))
+                    .collect()
+            })
+            .collect();
+        for sum in combinations::<K>(&shortlists) {
+            let terms: Vec<[Model; K]> = (0..K)
+                .filter(|&i| sum[i] != Model::Constant)
+                .map(|i| {
+                    let mut factors = [Model::Constant; K];
+                    factors[i] = sum[i];
+                    factors
+                })
+                .collect();
+            if terms.len() >= 2 {
+                forms.push(terms);
+            }
+        }
+        forms
+    }
+
+    /// Fits every form to the sample, returning those that can compete, best
+    /// first.
+    fn fit_all(&self, forms: &[Vec<[Model; K]>], sample: &[([f64; K], f64)]) -> Vec<MultiFit> {
+        let points = sample.len();
+        let mut fits: Vec<MultiFit> = forms
+            .iter()
+            .filter_map(|form| self.fit(form, sample))
+            .filter(is_plausible)
+            .collect();
+        fits.sort_by(|a, b| cmp(corrected_error(a, points), corrected_error(b, points)));
+        fits
+    }
+
+    /// Fits one form by weighted least squares on its evaluated terms.
+    ///
+    /// Returns `None` when a term cannot be evaluated at some measured sizes —
+    /// a logarithm of zero — or the gains are not determined by the sample.
+    fn fit(&self, form: &[[Model; K]], sample: &[([f64; K], f64)]) -> Option<MultiFit> {
+        let magnitude = linalg::mean(sample.iter().map(|&(_, y)| y.abs()))?;
+        let floor = magnitude * SMALLEST_MEANINGFUL_SHARE;
+
+        let (gains, offset) = match form.is_empty() {
+            true => {
+                let offset = linalg::weighted_mean(
+                    sample
+                        .iter()
+                        .map(|&(_, y)| (y, fit::relative_weight(y, floor))),
+                )?;
+                (Vec::new(), offset)
+            }
+            false => {
+                let rows = sample
+                    .iter()
+                    .map(|(sizes, y)| {
+                        let x = form
+                            .iter()
+                            .map(|factors| product(factors, sizes))
+                            .collect::<Option<Vec<f64>>>()?;
+                        Some((x, *y, fit::relative_weight(*y, floor)))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let plane = linalg::fit_plane(&rows)?;
+                (plane.gains, plane.offset)
+            }
+        };
+
+        let terms: Vec<Term> = form
+            .iter()
+            .zip(gains)
+            .map(|(factors, gain)| Term {
+                factors: factors.to_vec(),
+                gain,
+            })
+            .collect();
+        let mut fit = MultiFit {
+            terms,
+            offset,
+            r_squared: 0.0,
+            relative_error: 0.0,
+            names: self.names.clone(),
+        };
+        let predicted: Vec<(f64, f64)> = sample
+            .iter()
+            .map(|(sizes, y)| (*y, fit.evaluate(sizes)))
+            .collect();
+        (fit.r_squared, fit.relative_error) = fit::score_predictions(&predicted)?;
+        Some(fit)
+    }
+
+    /// Fraction of resamples that choose the same form, as
+    /// [`Analysis`](crate::Analysis) estimates its confidence.
+    fn confidence(
+        &self,
+        forms: &[Vec<[Model; K]>],
+        sample: &[([f64; K], f64)],
+        best: &MultiFit,
+    ) -> f64 {
+        let chosen = analysis::each_resample(self.resamples, |index| {
+            let mut rng = Rng::stream(self.seed, index as u64);
+            let drawn: Vec<([f64; K], f64)> = (0..sample.len())
+                .filter_map(|_| sample.get(rng.below(sample.len())).copied())
+                .collect();
+            let resample = prepare(&drawn);
+            // A draw left too thin is not counted, as in `Analysis`.
+            if resample.len() < data::MIN_POINTS {
+                return None;
+            }
+            let refitted = self.fit_all(forms, &resample);
+            Some(select(&refitted, resample.len()).is_some_and(|fit| fit.same_form(best)))
+        });
+
+        let compared = chosen.iter().flatten().count();
+        let agreed = chosen.iter().flatten().filter(|&&agreed| agreed).count();
+        match compared {
+            0 => 0.0,
+            _ => agreed as f64 / compared as f64,
+        }
+    }
+
+    /// Collects everything about the sample that weakens the inference.
+    ///
+    /// The checks on the direction of the cost that [`Analysis`] makes need an
+    /// order on the sizes, which several of them do not have, and are not made.
+    ///
+    /// [`Analysis`]: crate::Analysis
+    fn warnings(&self, sample: &[([f64; K], f64)]) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+
+        if sample.len() < self.advised_points {
+            warnings.push(Warning::TooFewPoints {
+                got: sample.len(),
+                advised: self.advised_points,
+            });
+        }
+
+        let narrowest = (0..K)
+            .map(|i| decades(sample.iter().map(|(sizes, _)| sizes[i])))
+            .min_by(|a, b| cmp(*a, *b));
+        if let Some(decades) = narrowest.filter(|&decades| decades < self.advised_decades) {
+            warnings.push(Warning::NarrowRange {
+                decades,
+                advised: self.advised_decades,
+            });
+        }
+
+        for i in 0..K {
+            for j in i + 1..K {
+                let pairs = sample.iter().map(|(sizes, _)| (sizes[i], sizes[j]));
+                if let Some(correlation) = log_correlation(pairs) {
+                    if correlation.abs() > CORRELATION_LIMIT {
+                        warnings.push(Warning::CorrelatedSizes {
+                            first: self.names[i].clone(),
+                            second: self.names[j].clone(),
+                            correlation,
+                        });
+                    }
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Every way of choosing one of `choices` for each of `K` positions.
+fn assignments<const K: usize>(choices: &[Model]) -> Vec<[Model; K]> {
+    combinations(&vec![choices.to_vec(); K])
+}
+
+/// Every way of choosing one of `choices[i]` for each position `i`.
+fn combinations<const K: usize>(choices: &[Vec<Model>]) -> Vec<[Model; K]> {
+    let mut all = vec![[Model::Constant; K]];
+    for (position, choices) in choices.iter().enumerate() {
+        all = all
+            .into_iter()
+            .flat_map(|partial| {
+                choices.iter().map(move |&choice| {
+                    let mut next = partial;
+                    next[position] = choice;
+                    next
+                })
+            })
+            .collect();
+    }
+    all
+}
+
+/// The product of one factor per size at `sizes`, or `None` if some factor
+/// has no finite value there.
+fn product(factors: &[Model], sizes: &[f64]) -> Option<f64> {
+    let value: f64 = factors
+        .iter()
+        .zip(sizes)
+        .map(|(factor, &x)| match factor {
+            Model::Constant => 1.0,
+            Model::Logarithmic => x.ln(),
+            Model::CubeRoot => x.cbrt(),
+            Model::SquareRoot => x.sqrt(),
+            Model::Linear => x,
+            Model::Linearithmic => x * x.ln(),
+            Model::Quadratic => x.powi(2),
+            Model::Cubic => x.powi(3),
+            _ => f64::NAN,
+        })
+        .product();
+    value.is_finite().then_some(value)
+}
+
+/// Drops non-finite measurements, collapses repeated sizes to their median
+/// cost, and sorts by the sizes.
+fn prepare<const K: usize>(data: &[([f64; K], f64)]) -> Vec<([f64; K], f64)> {
+    let mut finite: Vec<([f64; K], f64)> = data
+        .iter()
+        .copied()
+        .filter(|(sizes, y)| sizes.iter().all(|x| x.is_finite()) && y.is_finite())
+        .collect();
+    finite.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    let mut points: Vec<([f64; K], f64)> = Vec::with_capacity(finite.len());
+    let mut start = 0;
+    while start < finite.len() {
+        let sizes = finite[start].0;
+        let end = start
+            + finite[start..]
+                .iter()
+                .take_while(|(s, _)| *s == sizes)
+                .count();
+        let mut costs: Vec<f64> = finite[start..end].iter().map(|&(_, y)| y).collect();
+        if let Some(median) = linalg::median(&mut costs) {
+            points.push((sizes, median));
+        }
+        start = end;
+    }
+    points
+}
+
+/// How many decades the values span, or zero if any is not positive.
+fn decades(values: impl Iterator<Item = f64>) -> f64 {
+    let (mut smallest, mut largest) = (f64::INFINITY, f64::NEG_INFINITY);
+    for value in values {
+        smallest = smallest.min(value);
+        largest = largest.max(value);
+    }
+    match smallest > 0.0 && largest.is_finite() {
+        true => (largest / smallest).log10().max(0.0),
+        false => 0.0,
+    }
+}
+
+/// Pearson correlation of the logarithms of each pair, or `None` where it is
+/// undefined: a size that is not positive, or one that does not vary.
+///
+/// In logarithms because that is the scale the forms differ on — sizes that
+/// double together are as inseparable at a thousand as at ten.
+fn log_correlation(pairs: impl Iterator<Item = (f64, f64)>) -> Option<f64> {
+    let logs: Vec<(f64, f64)> = pairs
+        .map(|(a, b)| (a > 0.0 && b > 0.0).then(|| (a.ln(), b.ln())))
+        .collect::<Option<_>>()?;
+    let mean_a = linalg::mean(logs.iter().map(|&(a, _)| a))?;
+    let mean_b = linalg::mean(logs.iter().map(|&(_, b)| b))?;
+
+    let (mut saa, mut sbb, mut sab) = (0.0, 0.0, 0.0);
+    for (a, b) in logs {
+        saa += (a - mean_a) * (a - mean_a);
+        sbb += (b - mean_b) * (b - mean_b);
+        sab += (a - mean_a) * (b - mean_b);
+    }
+    let correlation = sab / (saa * sbb).sqrt();
+    correlation.is_finite().then_some(correlation)
+}
+
+/// Whether every term adds cost, and a constant is not negative.
+fn is_plausible(fit: &MultiFit) -> bool {
+    match fit.terms.is_empty() {
+        true => fit.offset >= 0.0,
+        false => fit.terms.iter().all(|term| term.gain >= 0.0),
+    }
+}
+
+/// The fit's error, corrected for the parameters it spent.
+fn corrected_error(fit: &MultiFit, points: usize) -> f64 {
+    analysis::corrected(fit.relative_error, fit.parameters(), points)
+}
+
+/// Total ordering over scores, treating any incomparable pair as equal.
+fn cmp(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Picks the form to report from fits ordered best-scoring first.
+///
+/// Among the forms that fit about as well as the best one, the one with the
+/// fewest terms wins, and between forms with as many terms the better fit
+/// does — the single-size selection, with the number of terms standing in for
+/// how much freedom a model has to bend towards the noise.
+fn select(fitted: &[MultiFit], points: usize) -> Option<MultiFit> {
+    let best = fitted.first()?;
+
+    fitted
+        .iter()
+        .filter(|fit| {
+            analysis::scores_as_well_as(
+                corrected_error(fit, points),
+                fit.parameters(),
+                corrected_error(best, points),
+                best.parameters(),
+            )
+        })
+        .min_by(|a, b| {
+            a.parameters()
+                .cmp(&b.parameters())
+                .then(cmp(corrected_error(a, points), corrected_error(b, points)))
+        })
+        .or(Some(best))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sizes from 10 to 10^4, six to an axis, combined into a grid.
+    fn grid(cost: impl Fn(f64, f64) -> f64) -> Vec<([f64; 2], f64)> {
+        let axis: Vec<f64> = (0..6).map(|k| 10f64.powf(1.0 + 0.6 * k as f64)).collect();
+        axis.iter()
+            .flat_map(|&n| axis.iter().map(move |&m| ([n, m], 0.0)))
+            .map(|([n, m], _)| ([n, m], cost(n, m)))
+            .collect()
+    }
+
+    /// Sizes from 10 to 10^3, four to an axis, combined into a cube.
+    fn cube(cost: impl Fn(f64, f64, f64) -> f64) -> Vec<([f64; 3], f64)> {
+        let axis: Vec<f64> = (0..4)
+            .map(|k| 10f64.powf(1.0 + 2.0 * k as f64 / 3.0))
+            .collect();
+        let mut data = Vec::new();
+        for &a in &axis {
+            for &b in &axis {
+                for &c in &axis {
+                    data.push(([a, b, c], cost(a, b, c)));
+                }
+            }
+        }
+        data
+    }
+
+    fn inferred(cost: impl Fn(f64, f64) -> f64) -> MultiInference {
+        MultiAnalysis::new(["n", "m"])
+            .infer(&grid(cost))
+            .expect("a grid is inferable")
+    }
+
+    #[test]
+    fn infers_a_sum_of_sizes() {
+        let inference = MultiAnalysis::new(["V", "E"])
+            .infer(&grid(|v, e| 3.0 * v + 5.0 * e + 40.0))
+            .unwrap();
+
+        assert_eq!(inference.best.to_string(), "O(V + E)");
+        assert!(inference.confidence > 0.9);
+        assert!(inference.warnings.is_empty(), "{:?}", inference.warnings);
+    }
+
+    #[test]
+    fn infers_a_product_of_sizes() {
+        let inference = inferred(|n, m| 2.0 * n * m + 100.0);
+
+        assert_eq!(inference.best.to_string(), "O(n*m)");
+        assert_eq!(inference.best.terms[0].factors, [Model::Linear; 2]);
+        assert!((inference.best.terms[0].gain - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn infers_a_logarithm_in_one_size() {
+        let inference = inferred(|n, m| 4.0 * n * m.ln() + 10.0);
+
+        assert_eq!(inference.best.to_string(), "O(n log m)");
+    }
+
+    #[test]
+    fn infers_a_constant() {
+        let inference = inferred(|_, _| 7.0);
+
+        assert_eq!(inference.best.to_string(), "O(1)");
+        assert!((inference.best.offset - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn prefers_the_sizes_that_matter() {
+        // Reaching for the sum would spend a parameter on a size that does not
+        // affect the cost.
+        let inference = inferred(|n, _| n * n);
+
+        assert_eq!(inference.best.to_string(), "O(n^2)");
+    }
+
+    #[test]
+    fn warns_about_sizes_that_rise_together() {
+        let data: Vec<([f64; 2], f64)> = (0..12)
+            .map(|k| {
+                let v = 2f64.powi(k + 3);
+                ([v, 2.0 * v], v + v)
+            })
+            .collect();
+
+        let inference = MultiAnalysis::new(["V", "E"]).infer(&data).unwrap();
+
+        assert!(inference.warnings.iter().any(|w| matches!(
+            w,
+            Warning::CorrelatedSizes { first, second, correlation }
+                if first == "V" && second == "E" && *correlation > 0.99
+        )));
+    }
+
+    #[test]
+    fn reports_the_narrowest_range() {
+        let data: Vec<([f64; 2], f64)> = grid(|n, m| n + m)
+            .into_iter()
+            .map(|([n, m], y)| ([n, 100.0 + m / 1000.0], y))
+            .collect();
+
+        let inference = MultiAnalysis::new(["n", "m"]).infer(&data).unwrap();
+
+        assert!(inference.warnings.iter().any(|w| matches!(
+            w,
+            Warning::NarrowRange { decades, .. } if *decades < 0.1
+        )));
+    }
+
+    #[test]
+    fn needs_three_distinct_combinations_of_sizes() {
+        let data = [([1., 1.], 1.), ([1., 1.], 2.), ([2., 1.], 3.)];
+
+        let err = MultiAnalysis::new(["n", "m"]).infer(&data).unwrap_err();
+
+        assert_eq!(err, Error::NotEnoughData { needed: 3, got: 2 });
+    }
+
+    #[test]
+    fn restricting_the_factors_restricts_the_forms() {
+        let analysis = MultiAnalysis::new(["n", "m"]).factors([Model::Linear, Model::Exponential]);
+
+        // O(1), three products and one sum.
+        assert_eq!(analysis.forms(&grid(|n, m| n + m)).len(), 5);
+    }
+
+    #[test]
+    fn sums_are_built_from_the_terms_that_fit_on_their_own() {
+        let analysis = MultiAnalysis::new(["a", "b", "c"]);
+        let forms = analysis.forms(&cube(|a, b, c| a + b + c));
+
+        let products = forms.iter().filter(|form| form.len() == 1).count();
+        let sums = forms.iter().filter(|form| form.len() >= 2).count();
+        assert_eq!(products, 8usize.pow(3) - 1);
+        // Pairs of sizes and all three, each with one of three factors.
+        assert_eq!(sums, 3 * 3 * 3 + 3 * 3 * 3);
+    }
+
+    #[test]
+    fn infers_in_three_sizes() {
+        let inference = MultiAnalysis::new(["a", "b", "c"])
+            .resamples(20)
+            .infer(&cube(|a, b, _| a + 4.0 * b * b + 9.0))
+            .unwrap();
+
+        assert_eq!(inference.best.to_string(), "O(a + b^2)");
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 3 input sizes")]
+    fn refuses_more_sizes_than_it_can_enumerate() {
+        MultiAnalysis::new(["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn the_seed_and_resamples_are_the_callers() {
+        let data = grid(|n, m| n * (1.0 + 0.3 * (n * m).sin()) + m);
+        let confidence = |analysis: MultiAnalysis<2>| analysis.infer(&data).unwrap().confidence;
+
+        assert_eq!(confidence(MultiAnalysis::new(["n", "m"]).resamples(0)), 0.0);
+        assert_eq!(
+            confidence(MultiAnalysis::new(["n", "m"]).seed(7)),
+            confidence(MultiAnalysis::new(["n", "m"]).seed(7))
+        );
+    }
+
+    #[test]
+    fn writes_powers_before_logarithms() {
+        let names = vec!["n".to_string(), "m".to_string()];
+
+        assert_eq!(
+            notation(&[Model::Linearithmic, Model::Linear], &names),
+            "n*m log n"
+        );
+        assert_eq!(
+            notation(&[Model::Logarithmic, Model::Logarithmic], &names),
+            "log n log m"
+        );
+        assert_eq!(notation(&[Model::Constant, Model::Cubic], &names), "m^3");
+    }
+}
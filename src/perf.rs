@@ -0,0 +1,246 @@
+//! Counting what a routine costs in instructions rather than timing it.
+//!
+//! A timing on a shared machine carries every other tenant's work in it, and
+//! the noise that leaves is what keeps `confidence` low in CI. The number of
+//! instructions a routine retires hardly depends on what else the machine is
+//! doing, so a complexity asserted on it holds still enough to gate a merge.
+//! Linux counts them for any process that asks through `perf_event_open`.
+
+use crate::error::Error;
+use std::io;
+use std::marker::PhantomData;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// `PERF_TYPE_HARDWARE`: counters in the CPU's performance monitoring unit.
+const TYPE_HARDWARE: u32 = 0;
+
+/// `PERF_TYPE_SOFTWARE`: counters the kernel keeps itself.
+const TYPE_SOFTWARE: u32 = 1;
+
+/// `PERF_COUNT_HW_INSTRUCTIONS`: instructions retired.
+const HW_INSTRUCTIONS: u64 = 1;
+
+/// `PERF_COUNT_SW_TASK_CLOCK`: nanoseconds the task was on a CPU.
+const SW_TASK_CLOCK: u64 = 1;
+
+/// `perf_event_attr` flag bits: start disabled, and count only the routine's
+/// own user-space work — which is also all an unprivileged process may count
+/// under the default `perf_event_paranoid` of 2.
+const DISABLED: u64 = 1;
+const EXCLUDE_KERNEL: u64 = 1 << 5;
+const EXCLUDE_HV: u64 = 1 << 6;
+
+/// `PERF_FLAG_FD_CLOEXEC`.
+const FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+/// `PERF_EVENT_IOC_ENABLE`, `_DISABLE` and `_RESET`: `_IO('$', 0)`, `1` and `3`.
+const IOC_ENABLE: libc::Ioctl = 0x2400;
+const IOC_DISABLE: libc::Ioctl = 0x2401;
+const IOC_RESET: libc::Ioctl = 0x2403;
+
+/// The first 64 bytes of `perf_event_attr`, its original layout. The kernel
+/// reads the size from the struct and takes the fields it does not see as zero.
+#[repr(C)]
+#[derive(Default)]
+struct Attributes {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+/// What a [`PerfCounter`] counts.
+///
+/// Non-exhaustive: a later release may count something else.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Event {
+    /// Instructions retired in user space. The same from run to run to within
+    /// a fraction of a percent, whatever else the machine is doing.
+    Instructions,
+
+    /// Nanoseconds the thread spent on a CPU. Not a count of work, but free of
+    /// the time spent waiting for one, so steadier than a wall clock. What a
+    /// virtual machine that exposes no hardware counters offers instead.
+    TaskClock,
+}
+
+/// A Linux performance counter on the calling thread.
+///
+/// Counts only what the thread that opened it does, in user space: work the
+/// routine hands to other threads, or to the kernel, is not counted. For the
+/// same reason it is neither `Send` nor `Sync`: measured from another thread,
+/// it would report the opener's count, which is next to nothing.
+///
+/// ```compile_fail
+/// fn send<T: Send>(_: T) {}
+/// send(big_o::PerfCounter::new());
+/// ```
+///
+/// # Example
+/// ```
+/// use big_o::{Event, Harness, Model, PerfCounter};
+///
+/// // Instructions where the CPU counts them, CPU time where it does not, and
+/// // nothing at all where the kernel forbids both.
+/// if let Ok(counter) = PerfCounter::new() {
+///     let inference = Harness::new()
+///         .ladder(1_000, 1_000_000, 7)
+///         .repeats(3)
+///         .infer_counted(
+///             &counter,
+///             |n| (0..n as u64).collect::<Vec<_>>(),
+///             |input| input.iter().sum::<u64>(),
+///         )
+///         .unwrap();
+///
+///     if counter.event() == Event::Instructions {
+///         assert_eq!(inference.best.model, Model::Linear);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PerfCounter {
+    fd: OwnedFd,
+    event: Event,
+    /// Ties the counter to the thread it counts.
+    thread: PhantomData<*const ()>,
+}
+
+impl PerfCounter {
+    /// Opens a counter of instructions retired, or of task clock where the
+    /// hardware counters are not available — inside most virtual machines.
+    ///
+    /// # Errors
+    /// [`Error::CounterUnavailable`] if neither can be opened: the kernel was
+    /// built without `perf_event`, a seccomp filter blocks the call, or
+    /// `/proc/sys/kernel/perf_event_paranoid` is above 2.
+    pub fn new() -> Result<Self, Error> {
+        Self::open(Event::Instructions).or_else(|_| Self::open(Event::TaskClock))
+    }
+
+    /// Opens a counter of `event`, and of nothing else.
+    ///
+    /// # Errors
+    /// [`Error::CounterUnavailable`] if it cannot be opened.
+    pub fn open(event: Event) -> Result<Self, Error> {
+        let (kind, config) = match event {
+            Event::Instructions => (TYPE_HARDWARE, HW_INSTRUCTIONS),
+            Event::TaskClock => (TYPE_SOFTWARE, SW_TASK_CLOCK),
+        };
+        let attributes = Attributes {
+            kind,
+            size: std::mem::size_of::<Attributes>() as u32,
+            config,
+            flags: DISABLED | EXCLUDE_KERNEL | EXCLUDE_HV,
+            ..Attributes::default()
+        };
+        // SAFETY: `attributes` is a valid `perf_event_attr` of the size it
+        // declares, and outlives the call. The rest are plain integers: this
+        // thread, any CPU, no group.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attributes as *const Attributes,
+                0 as libc::pid_t,
+                -1 as libc::c_int,
+                -1 as libc::c_int,
+                FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(Error::CounterUnavailable {
+                errno: io::Error::last_os_error().raw_os_error().unwrap_or(0),
+            });
+        }
+        // SAFETY: the call returned a new descriptor, which nothing else owns.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) };
+        Ok(Self {
+            fd,
+            event,
+            thread: PhantomData,
+        })
+    }
+
+    /// What this counter counts.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Runs `routine` and reports what it cost in this counter's
+    /// [`event`](Self::event), along with what it returned.
+    ///
+    /// # Panics
+    /// If the kernel refuses to start, stop or read a counter it opened.
+    pub fn measure<R>(&self, routine: impl FnOnce() -> R) -> (R, u64) {
+        self.control(IOC_RESET);
+        self.control(IOC_ENABLE);
+        let result = routine();
+        self.control(IOC_DISABLE);
+
+        let mut count = [0u8; 8];
+        // SAFETY: the buffer is eight writable bytes, the size of the count.
+        let read = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                count.as_mut_ptr() as *mut libc::c_void,
+                count.len(),
+            )
+        };
+        assert!(
+            read == count.len() as isize,
+            "a performance counter could not be read: {}",
+            io::Error::last_os_error()
+        );
+        (result, u64::from_ne_bytes(count))
+    }
+
+    /// Issues one of the `PERF_EVENT_IOC_*` requests that take no argument.
+    fn control(&self, request: libc::Ioctl) {
+        // SAFETY: the descriptor is a perf event this counter owns, and the
+        // request takes no pointer.
+        let done = unsafe { libc::ioctl(self.fd.as_raw_fd(), request, 0) };
+        assert!(
+            done == 0,
+            "a performance counter refused a request: {}",
+            io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hint::black_box;
+
+    #[test]
+    fn the_attributes_are_the_original_layout() {
+        assert_eq!(std::mem::size_of::<Attributes>(), 64);
+    }
+
+    #[test]
+    fn more_work_counts_more() {
+        // A sandbox may forbid every counter, and there is nothing to test
+        // then, unless the runner says it has them.
+        let counter = match PerfCounter::new() {
+            Ok(counter) => counter,
+            Err(err) if std::env::var_os("PERF_REQUIRED").is_some() => {
+                panic!("PERF_REQUIRED is set, but {err}")
+            }
+            Err(_) => return,
+        };
+        let sum = |n: u64| (0..n).map(black_box).sum::<u64>();
+
+        let (_, small) = counter.measure(|| sum(10_000));
+        let (_, large) = counter.measure(|| sum(1_000_000));
+
+        assert!(large > 10 * small, "{small} then {large}");
+    }
+}
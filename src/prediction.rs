@@ -0,0 +1,320 @@
+//! What a fit or an inference expects the cost to be at an input size, and how
+//! far from that a measurement there could plausibly land.
+//!
+//! A capacity question — how long at ten times today's input? — answered with a
+//! single number reads as more certain than the measurements behind it are. So
+//! every prediction carries a band: the scatter the measurements showed about
+//! the fit, and, for an inference, the disagreement between the models the
+//! resamples could not tell apart.
+
+use crate::analysis::Inference;
+use crate::fit::Fit;
+use std::fmt;
+
+/// How many standard deviations of scatter the band extends either side of
+/// the prediction: about 95% of measurements, were the noise Gaussian.
+const SPREAD: f64 = 2.0;
+
+/// An expected cost at one input size, with the band a measurement there
+/// would be expected to land in.
+///
+/// The band is in the units of the cost, and never reaches below zero. It
+/// grows with the prediction, because the scatter it is drawn from is
+/// relative: a fit that was typically 5% out is taken to be 5% out at any
+/// input size. When the fit carries its [`uncertainty`](Fit::uncertainty),
+/// the band also spans how far each coefficient could be off, which grows
+/// with the distance extrapolated: an exponent fitted a little high compounds
+/// far past the largest input measured. Without it, the band understates that
+/// risk.
+///
+/// # Example
+/// ```
+/// let data: Vec<(f64, f64)> = (1..=20)
+///     .map(|n| n as f64 * 100.0)
+///     .map(|n| (n, 3.0 * n * (1.0 + 0.04 * (n / 100.0).sin())))
+///     .collect();
+///
+/// let inference = big_o::infer_complexity(&data).unwrap();
+/// let prediction = inference.predict(20_000.0);
+///
+/// assert!(prediction.lower < 60_000.0 && 60_000.0 < prediction.upper);
+/// ```
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Prediction {
+    /// The expected cost.
+    pub value: f64,
+
+    /// The low end of the band.
+    pub lower: f64,
+
+    /// The high end of the band.
+    pub upper: f64,
+}
+
+impl Prediction {
+    /// The band `SPREAD` standard deviations either side of `value`, with
+    /// its low end held at zero: no routine costs less than nothing.
+    fn around(value: f64, deviation: f64) -> Prediction {
+        let lower = value - SPREAD * deviation;
+        Prediction {
+            value,
+            // Not `max`, which would turn a NaN into zero.
+            lower: if lower < 0.0 { 0.0 } else { lower },
+            upper: value + SPREAD * deviation,
+        }
+    }
+}
+
+/// `value (lower to upper)`, each number written with the formatter's own
+/// options, so `{:.1}` rounds all three.
+impl fmt::Display for Prediction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)?;
+        f.write_str(" (")?;
+        fmt::Display::fmt(&self.lower, f)?;
+        f.write_str(" to ")?;
+        fmt::Display::fmt(&self.upper, f)?;
+        f.write_str(")")
+    }
+}
+
+impl Fit {
+    /// The cost this fit expects at `x`, with a band as wide as the scatter of
+    /// the measurements about it: [`relative_error`](Self::relative_error) of
+    /// the prediction, twice over either side. With an
+    /// [`uncertainty`](Self::uncertainty), the band is widened by how far the
+    /// prediction moves when each coefficient moves by its standard error.
+    ///
+    /// The value is [`evaluate`](Self::evaluate)`(x)`. Not finite where that
+    /// is not.
+    ///
+    /// ```
+    /// let data = [(1., 2.), (2., 4.), (3., 6.), (4., 8.), (5., 10.)];
+    /// let best = big_o::infer_complexity(&data).unwrap().best;
+    ///
+    /// let prediction = best.predict(50.0);
+    /// assert!((prediction.value - 100.0).abs() < 1e-6);
+    /// assert!(prediction.upper - prediction.lower < 1e-6, "the line is exact");
+    /// ```
+    pub fn predict(&self, x: f64) -> Prediction {
+        let value = self.evaluate(x);
+        Prediction::around(value, self.deviation(x, value))
+    }
+
+    /// The standard deviation of measurements about a prediction of `value`
+    /// at `x`.
+    ///
+    /// The scatter, plus the shift in the prediction from moving each
+    /// coefficient by its standard error. The coefficients are taken to move
+    /// independently, which overstates the band where a gain and an offset
+    /// trade off against each other; but at sizes past the measured ones the
+    /// term that grows with `x` dominates, and that is where the band matters.
+    fn deviation(&self, x: f64, value: f64) -> f64 {
+        let scatter = self.relative_error * value.abs();
+        let Some(uncertainty) = self.uncertainty else {
+            return scatter;
+        };
+        let errors = uncertainty.standard_error.coefficients();
+        let coefficients = (0..errors.len())
+            .map(|i| {
+                let moved = self
+                    .params
+                    .map(|j, coefficient| match j == i {
+                        true => coefficient + errors[i],
+                        false => coefficient,
+                    })
+                    .evaluate(x);
+                (moved - value).powi(2)
+            })
+            .filter(|shift| shift.is_finite())
+            .sum::<f64>();
+        (scatter * scatter + coefficients).sqrt()
+    }
+}
+
+impl Inference {
+    /// The cost expected at `x`, averaged over every model a resample chose,
+    /// each weighted by its share of [`votes`](Self::votes).
+    ///
+    /// The band covers both the scatter of the measurements about each model
+    /// and the models' disagreement with one another: where the data could
+    /// not decide between `O(n log n)` and `O(n^1.1)`, the prediction at a
+    /// large input is somewhere between them, and the band spans both.
+    ///
+    /// With no votes — no resamples were drawn, or the inference was stored
+    /// before votes were reported — this is [`best`](Self::best)'s prediction.
+    pub fn predict(&self, x: f64) -> Prediction {
+        let plausible: Vec<(f64, f64, f64)> = self
+            .votes
+            .iter()
+            .filter_map(|vote| {
+                let fit = self.all.iter().find(|fit| fit.model == vote.model)?;
+                let value = fit.evaluate(x);
+                Some((vote.share, value, fit.deviation(x, value)))
+            })
+            .collect();
+        let total: f64 = plausible.iter().map(|&(share, ..)| share).sum();
+        if total <= 0.0 {
+            return self.best.predict(x);
+        }
+
+        let value = plausible
+            .iter()
+            .map(|&(share, value, _)| share * value)
+            .sum::<f64>()
+            / total;
+        // The variance of the mixture: each model's own scatter, plus how far
+        // its prediction is from the average.
+        let variance = plausible
+            .iter()
+            .map(|&(share, predicted, deviation)| {
+                share * (deviation * deviation + (predicted - value).powi(2))
+            })
+            .sum::<f64>()
+            / total;
+        Prediction::around(value, variance.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Vote;
+    use crate::fit::{fit_of, ModelParams};
+    use crate::model::Model;
+    use crate::uncertainty::Uncertainty;
+
+    fn line(gain: f64, relative_error: f64) -> Fit {
+        fit_of(
+            Model::Linear,
+            ModelParams::Linear { gain, offset: 0.0 },
+            relative_error,
+        )
+    }
+
+    fn square(gain: f64, relative_error: f64) -> Fit {
+        fit_of(
+            Model::Quadratic,
+            ModelParams::Quadratic { gain, offset: 0.0 },
+            relative_error,
+        )
+    }
+
+    fn inference(all: Vec<Fit>, votes: Vec<Vote>) -> Inference {
+        Inference {
+            best: all[0],
+            all,
+            criteria: Vec::new(),
+            confidence: votes.first().map_or(0.0, |vote| vote.share),
+            votes,
+            range: None,
+            influence: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn a_fit_predicts_within_twice_its_relative_error() {
+        let prediction = line(3.0, 0.05).predict(100.0);
+
+        assert_eq!(prediction.value, 300.0);
+        assert!((prediction.lower - 270.0).abs() < 1e-9);
+        assert!((prediction.upper - 330.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_band_never_reaches_below_zero() {
+        let prediction = line(3.0, 0.8).predict(100.0);
+
+        assert_eq!(prediction.lower, 0.0);
+        assert!((prediction.upper - 780.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_uncertain_exponent_widens_the_band_the_further_it_extrapolates() {
+        let power = |power| ModelParams::Polynomial {
+            gain: 1.0,
+            power,
+            offset: 0.0,
+        };
+        let fit = Fit {
+            model: Model::Polynomial,
+            params: power(1.5),
+            uncertainty: Some(Uncertainty {
+                level: 0.95,
+                standard_error: ModelParams::Polynomial {
+                    gain: 0.0,
+                    power: 0.05,
+                    offset: 0.0,
+                },
+                lower: power(1.4),
+                upper: power(1.6),
+            }),
+            ..line(0.0, 0.05)
+        };
+        let above = |x: f64| {
+            let prediction = fit.predict(x);
+            (prediction.upper - prediction.value) / prediction.value
+        };
+
+        // Moving the exponent by 0.05 moves the prediction by x^0.05 - 1.
+        let shift = 1e6f64.powf(0.05) - 1.0;
+        let expected = 2.0 * (0.05f64.powi(2) + shift.powi(2)).sqrt();
+        assert!((above(1e6) - expected).abs() < 1e-9, "{}", above(1e6));
+        assert!(above(1e3) < above(1e6));
+        assert!(above(1e3) > 2.0 * 0.05, "wider than the scatter alone");
+    }
+
+    #[test]
+    fn a_unanimous_inference_predicts_as_its_fit_does() {
+        let inference = inference(
+            vec![line(3.0, 0.05), square(1.0, 0.2)],
+            vec![Vote {
+                model: Model::Linear,
+                share: 1.0,
+            }],
+        );
+
+        assert_eq!(inference.predict(100.0), line(3.0, 0.05).predict(100.0));
+    }
+
+    #[test]
+    fn disagreeing_models_widen_the_band_to_span_both() {
+        let inference = inference(
+            vec![line(100.0, 0.01), square(1.0, 0.01)],
+            vec![
+                Vote {
+                    model: Model::Linear,
+                    share: 0.5,
+                },
+                Vote {
+                    model: Model::Quadratic,
+                    share: 0.5,
+                },
+            ],
+        );
+
+        let prediction = inference.predict(1000.0);
+
+        assert!((prediction.value - 550_000.0).abs() < 1e-6);
+        assert!(prediction.lower < 100_000.0 && 1_000_000.0 < prediction.upper);
+    }
+
+    #[test]
+    fn without_votes_the_best_fit_predicts() {
+        let inference = inference(vec![line(3.0, 0.05)], Vec::new());
+
+        assert_eq!(inference.predict(7.0), line(3.0, 0.05).predict(7.0));
+    }
+
+    #[test]
+    fn displays_every_number_at_the_precision_asked_for() {
+        let prediction = line(3.0, 0.05).predict(100.0);
+
+        assert_eq!(format!("{prediction:.1}"), "300.0 (270.0 to 330.0)");
+    }
+}
@@ -0,0 +1,445 @@
+//! Cost curves that change shape partway: a working set outgrowing a cache, or
+//! a hybrid algorithm switching strategy at a threshold.
+//!
+//! One fit across such a sample averages the regimes into an answer that
+//! describes neither. `O(n)` that triples its constant where the data stops
+//! fitting in cache reads as `O(n^1.3)` — a growth rate the code does not have
+//! at any size. Fitting each regime on its own recovers the `O(n)` on both
+//! sides of the cliff, and the cliff itself.
+
+use crate::analysis::{self, Analysis, Inference};
+use crate::data::{self, Sample};
+use crate::error::Error;
+use crate::fit::Fit;
+use crate::measurement::{self, Measurement};
+use crate::selection::Selection;
+
+/// Fewest input sizes a regime is fitted from. Three determine a two-parameter
+/// fit with one to spare, which a breakpoint placed to flatter the noise can
+/// always make look exact; four leave a residual that means something.
+const MIN_REGIME_POINTS: usize = 4;
+
+/// Most regimes a sample is split into. Enough for the cliffs out of L1, L2
+/// and L3 in one sweep; more would be finding structure in the noise.
+const MAX_REGIMES: usize = 4;
+
+/// Relative error below which a single fit is taken to average over nothing
+/// worth reporting. No timer is steadier than a tenth of a percent, so a fit
+/// that close to every measurement has no regimes left to hide; its residuals
+/// are rounding, and often patterned ones.
+const NEGLIGIBLE_ERROR: f64 = 1e-3;
+
+/// The runs-test statistic below which a fit's residuals are taken to hide a
+/// change of regime: one-sided, at 5%. Residuals of a fit to one regime pass
+/// it nineteen times in twenty, and those skip the search.
+const PATTERNED: f64 = -1.645;
+
+/// Most input sizes a breakpoint is looked for between. Every candidate run
+/// between two of them is fitted by every model, so a sample of hundreds of
+/// sizes is searched at a stride rather than at every gap, which places a
+/// breakpoint to within a few sizes of where it is.
+const MAX_CUTS: usize = 24;
+
+/// How much lower the BIC of a split sample must be than the BIC of one fit
+/// for the split to be reported.
+///
+/// Far above the ten usually read as very strong evidence, and by measurement.
+/// The criterion prices a breakpoint as one parameter, but a breakpoint chosen
+/// from every gap in the sample buys more fit than that. At ten, single-regime
+/// samples with 5% of timing noise were split up to one time in ten on the
+/// crate's accuracy sweep; at thirty, about once in a thousand — while a cliff
+/// that doubles the cost is still found nearly every time.
+const DECISIVE: f64 = 30.0;
+
+/// One stretch of input sizes over which the cost follows a single model, as
+/// reported by [`Analysis::regimes`].
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Regime {
+    /// The smallest input size measured in the regime.
+    pub from: f64,
+
+    /// The largest input size measured in the regime.
+    pub to: f64,
+
+    /// The inference drawn from this regime's measurements alone.
+    pub inference: Inference,
+}
+
+impl Analysis {
+    /// Splits `data` into the regimes it is best explained by, and infers the
+    /// complexity of each, smallest input sizes first.
+    ///
+    /// Takes measurements as [`infer`](Self::infer) does. Each regime is
+    /// inferred from the repeats measured within it, so it keeps their spread
+    /// and the weighting by variance that comes with it.
+    ///
+    /// A sample is split only where the split explains it decisively better
+    /// than one fit does, by the Bayesian information criterion, counting
+    /// every regime's parameters and every breakpoint against it. So a sample
+    /// with one regime comes back as one, inferred exactly as
+    /// [`infer`](Self::infer) would. [`infer`](Self::infer) raises
+    /// [`Warning::Piecewise`](crate::Warning::Piecewise) where this would
+    /// split.
+    ///
+    /// Each regime is inferred with this analysis' settings, so it carries its
+    /// own confidence and warnings — typically that it spans too narrow a
+    /// range to tell its model from the alternatives, since it is a fraction
+    /// of the sample.
+    ///
+    /// # Errors
+    /// As [`infer`](Self::infer).
+    ///
+    /// # Example
+    /// ```
+    /// use big_o::{Analysis, Model, Warning};
+    ///
+    /// // Linear, with the constant tripling once the data outgrows a cache.
+    /// let data: Vec<(f64, f64)> = (0..24)
+    ///     .map(|k| 10f64.powf(1.0 + k as f64 / 6.0))
+    ///     .map(|n| (n, if n < 5_000.0 { n } else { 3.0 * n }))
+    ///     .collect();
+    ///
+    /// let analysis = Analysis::new();
+    /// let whole = analysis.infer(&data).unwrap();
+    /// assert_ne!(whole.best.model, Model::Linear);
+    /// assert!(whole.warnings.iter().any(|w| matches!(w, Warning::Piecewise { .. })));
+    ///
+    /// let regimes = analysis.regimes(&data).unwrap();
+    /// assert_eq!(regimes.len(), 2);
+    /// assert!(regimes.iter().all(|r| r.inference.best.model == Model::Linear));
+    /// assert!(regimes[0].to < 5_000.0 && 5_000.0 <= regimes[1].from);
+    /// ```
+    pub fn regimes<M: Measurement>(
+        &self,
+        data: impl IntoIterator<Item = M>,
+    ) -> Result<Vec<Regime>, Error> {
+        let data: Vec<M> = data.into_iter().collect();
+        let (converted, _) = measurement::collect(&data);
+        let sample = self.prepare(&converted);
+        let points = sample.points();
+        if points.len() < data::MIN_POINTS {
+            return Err(Error::NotEnoughData {
+                needed: data::MIN_POINTS,
+                got: points.len(),
+            });
+        }
+
+        let mut bounds = vec![0];
+        bounds.extend(splits(self, &sample));
+        bounds.push(points.len());
+        // Each repeat goes to the regime whose first size it is not below, so
+        // each regime is inferred from its raw repeats, spread and all, rather
+        // than from the sizes they were aggregated into.
+        let starts: Vec<f64> = bounds[1..bounds.len() - 1]
+            .iter()
+            .map(|&start| points[start].0)
+            .collect();
+        let mut segments: Vec<Vec<M>> = (0..bounds.len() - 1).map(|_| Vec::new()).collect();
+        for (measurement, &(x, _)) in data.into_iter().zip(&converted) {
+            let regime = starts.iter().filter(|&&start| start <= x).count();
+            segments[regime].push(measurement);
+        }
+
+        bounds
+            .windows(2)
+            .zip(segments)
+            .map(|(pair, segment)| {
+                let regime = &points[pair[0]..pair[1]];
+                Ok(Regime {
+                    from: regime.first().map_or(f64::NAN, |&(x, _)| x),
+                    to: regime.last().map_or(f64::NAN, |&(x, _)| x),
+                    inference: self.infer(segment)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The input sizes at which `sample` changes regime, each midway in log space
+/// between the last size of one regime and the first of the next. Empty when
+/// one fit explains the sample as well.
+///
+/// The search fits every model to hundreds of runs of the sample, so it is
+/// made only when `best`, the single fit, leaves residuals that look like it:
+/// not negligible, and patterned. A regime the fit averages over leaves a long
+/// run of points on one side of it, where noise leaves them scattered.
+pub(crate) fn breakpoints(analysis: &Analysis, sample: &Sample, best: &Fit) -> Vec<f64> {
+    if best.relative_error < NEGLIGIBLE_ERROR || !is_patterned(best, sample) {
+        return Vec::new();
+    }
+    let points = sample.points();
+    splits(analysis, sample)
+        .into_iter()
+        .filter_map(|start| {
+            let (before, _) = *points.get(start.checked_sub(1)?)?;
+            let (after, _) = *points.get(start)?;
+            Some(match before > 0.0 {
+                true => (before * after).sqrt(),
+                false => (before + after) / 2.0,
+            })
+        })
+        .collect()
+}
+
+/// Whether the signs of `fit`'s residuals change fewer times than noise would
+/// leave them: the Wald–Wolfowitz runs test, one-sided, at [`PATTERNED`].
+fn is_patterned(fit: &Fit, sample: &Sample) -> bool {
+    let signs: Vec<bool> = sample
+        .points()
+        .iter()
+        .map(|&(x, y)| y - fit.evaluate(x))
+        .filter(|residual| *residual != 0.0)
+        .map(|residual| residual > 0.0)
+        .collect();
+    let above = signs.iter().filter(|&&above| above).count() as f64;
+    let below = signs.len() as f64 - above;
+    if above == 0.0 || below == 0.0 {
+        return !signs.is_empty();
+    }
+
+    let total = above + below;
+    let runs = 1.0 + signs.windows(2).filter(|pair| pair[0] != pair[1]).count() as f64;
+    let expected = 2.0 * above * below / total + 1.0;
+    let variance = (expected - 1.0) * (expected - 2.0) / (total - 1.0);
+    variance > 0.0 && (runs - expected) / variance.sqrt() < PATTERNED
+}
+
+/// Where `sample` splits into the runs of points that explain it best: the
+/// index at which each run after the first starts, for the split with the
+/// lowest BIC if it beats a single fit by [`DECISIVE`]. Empty if none does.
+///
+/// For each number of regimes, dynamic programming finds the boundaries that
+/// minimize the total squared relative residual; the number of regimes is then
+/// chosen by BIC. The residuals of separate fits add up because each is
+/// relative to its own measurement, so the split's error is on the same scale
+/// as the single fit's.
+fn splits(analysis: &Analysis, sample: &Sample) -> Vec<usize> {
+    let points = sample.points();
+    let n = points.len();
+    if n < 2 * MIN_REGIME_POINTS {
+        return Vec::new();
+    }
+    let analysis = analysis.in_sample();
+
+    // Where a regime may start or end: every gap in a short sample, evenly
+    // spaced ones in a long one.
+    let stride = (n + MAX_CUTS - 1) / MAX_CUTS;
+    let mut cuts: Vec<usize> = (0..n).step_by(stride).collect();
+    cuts.push(n);
+
+    // runs[i][j]: the squared relative residual and the parameters of the
+    // best fit to the points from cut i to cut j, if they are enough to be a
+    // regime and some model fits them.
+    let mut runs: Vec<Vec<Option<(f64, usize)>>> = vec![vec![None; cuts.len()]; cuts.len()];
+    for (i, &start) in cuts.iter().enumerate() {
+        for (j, &end) in cuts.iter().enumerate().skip(i + 1) {
+            if end - start < MIN_REGIME_POINTS {
+                continue;
+            }
+            let regime = sample.select(start..end);
+            let (fits, _) = analysis.fit_all(&regime);
+            runs[i][j] = analysis.select(&fits, end - start).map(|fit| {
+                let len = (end - start) as f64;
+                (
+                    len * fit.relative_error * fit.relative_error,
+                    analysis::parameters(&fit),
+                )
+            });
+        }
+    }
+    let last = cuts.len() - 1;
+    let bic = |residual: f64, parameters: usize| {
+        Selection::Bic.value((residual / n as f64).sqrt(), parameters, n)
+    };
+    let Some((residual, parameters)) = runs[0][last] else {
+        return Vec::new();
+    };
+    let single = bic(residual, parameters);
+
+    // best[r][j]: the least residual splitting the points up to cut j into
+    // r + 1 runs, and the cut the last of them starts at.
+    let mut best: Vec<Vec<Option<(f64, usize)>>> = vec![vec![None; cuts.len()]; MAX_REGIMES];
+    for j in 1..=last {
+        best[0][j] = runs[0][j].map(|(residual, _)| (residual, 0));
+    }
+    for r in 1..MAX_REGIMES {
+        for j in 1..=last {
+            best[r][j] = (1..j)
+                .filter_map(|i| {
+                    let (before, _) = best[r - 1][i]?;
+                    let (residual, _) = runs[i][j]?;
+                    Some((before + residual, i))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+        }
+    }
+
+    let mut chosen = (single - DECISIVE, Vec::new());
+    for r in 1..MAX_REGIMES {
+        let mut bounds = vec![last];
+        for level in (1..=r).rev() {
+            let Some(&end) = bounds.last() else {
+                break;
+            };
+            let Some((_, start)) = best[level][end] else {
+                break;
+            };
+            bounds.push(start);
+        }
+        if bounds.len() != r + 1 {
+            continue;
+        }
+        bounds.push(0);
+        bounds.reverse();
+
+        let Some((residual, parameters)) =
+            bounds
+                .windows(2)
+                .try_fold((0.0, r), |(residual, parameters), pair| {
+                    let (more, spent) = runs[pair[0]][pair[1]]?;
+                    Some((residual + more, parameters + spent))
+                })
+        else {
+            continue;
+        };
+        let value = bic(residual, parameters);
+        if value < chosen.0 {
+            let starts = bounds[1..r + 1].iter().map(|&cut| cuts[cut]);
+            chosen = (value, starts.collect());
+        }
+    }
+    chosen.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregation::Aggregation;
+    use crate::model::Model;
+
+    fn ladder(cost: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+        (0..24)
+            .map(|k| 10f64.powf(1.0 + k as f64 / 6.0))
+            .enumerate()
+            .map(|(i, n)| (n, cost(n) * (1.0 + 0.03 * (i as f64 * 2.1).sin())))
+            .collect()
+    }
+
+    #[test]
+    fn one_regime_is_left_whole() {
+        let sample = data::prepare(&ladder(|n| 5.0 * n * n.ln()), Aggregation::Median, None);
+
+        assert!(splits(&Analysis::new(), &sample).is_empty());
+        assert!(breakpoints(&Analysis::new(), &sample, &best(&sample)).is_empty());
+    }
+
+    #[test]
+    fn splits_at_a_cache_cliff() {
+        let sample = data::prepare(
+            &ladder(|n| match n < 5_000.0 {
+                true => n,
+                false => 3.0 * n,
+            }),
+            Aggregation::Median,
+            None,
+        );
+
+        let breaks = breakpoints(&Analysis::new(), &sample, &best(&sample));
+
+        assert_eq!(breaks.len(), 1);
+        assert!((breaks[0] / 5_000.0).log10().abs() < 1.0 / 6.0);
+    }
+
+    #[test]
+    fn finds_two_cliffs() {
+        let sample = data::prepare(
+            &ladder(|n| match n {
+                n if n < 300.0 => n,
+                n if n < 20_000.0 => 4.0 * n,
+                n => 16.0 * n,
+            }),
+            Aggregation::Median,
+            None,
+        );
+
+        assert_eq!(
+            breakpoints(&Analysis::new(), &sample, &best(&sample)).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn finds_a_change_of_model() {
+        // A hybrid that switches from a quadratic method to a linearithmic one.
+        let data = ladder(|n| match n < 2_000.0 {
+            true => n * n,
+            false => 300.0 * n * n.ln(),
+        });
+
+        let regimes = Analysis::new().regimes(&data).unwrap();
+
+        let models: Vec<Model> = regimes.iter().map(|r| r.inference.best.model).collect();
+        assert_eq!(models, [Model::Quadratic, Model::Linearithmic]);
+    }
+
+    #[test]
+    fn each_regime_keeps_the_spread_of_its_repeats() {
+        let data: Vec<(f64, f64)> = ladder(|n| match n < 5_000.0 {
+            true => n,
+            false => 3.0 * n,
+        })
+        .into_iter()
+        .flat_map(|(n, cost)| {
+            (0..data::MIN_REPEATS).map(move |i| (n, cost * (1.0 + 0.01 * (i as f64 * 1.7).sin())))
+        })
+        .collect();
+
+        let regimes = Analysis::new().regimes(&data).unwrap();
+
+        assert_eq!(regimes.len(), 2);
+        for regime in &regimes {
+            assert_eq!(regime.inference.best.model, Model::Linear);
+            assert!(
+                regime.inference.best.reduced_chi_squared.is_some(),
+                "fitted to the repeats, not their medians"
+            );
+        }
+    }
+
+    fn best(sample: &Sample) -> Fit {
+        let (fits, _) = Analysis::new().fit_all(sample);
+        Analysis::new()
+            .select(&fits, sample.points().len())
+            .unwrap()
+    }
+
+    #[test]
+    fn noise_about_the_fit_is_not_a_pattern_but_a_cliff_is() {
+        let fitted = |data: &[(f64, f64)]| {
+            let sample = data::prepare(data, Aggregation::Median, None);
+            is_patterned(&best(&sample), &sample)
+        };
+
+        assert!(!fitted(&ladder(|n| 2.0 * n)));
+        assert!(fitted(&ladder(|n| match n < 5_000.0 {
+            true => n,
+            false => 3.0 * n,
+        })));
+    }
+
+    #[test]
+    fn a_sample_too_short_to_split_is_one_regime() {
+        let data = ladder(|n| n);
+        let sample = data::prepare(
+            &data[..2 * MIN_REGIME_POINTS - 1],
+            Aggregation::Median,
+            None,
+        );
+
+        assert!(splits(&Analysis::new(), &sample).is_empty());
+    }
+}
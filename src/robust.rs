@@ -0,0 +1,276 @@
+//! Fitting that a few wild measurements cannot steer.
+//!
+//! Least squares lets every measurement pull on the fit in proportion to its
+//! squared error, so one run three times slower than its neighbours — a shared
+//! CI runner descheduling the benchmark — pulls harder than all the others
+//! agree. Collapsing repeats to a median guards a size that was measured
+//! several times; nothing guards a size measured once. A robust loss does, by
+//! refitting with each measurement weighted down by how far the last fit
+//! placed it from the rest.
+
+use crate::linalg;
+
+/// Iterations of reweighting per loss before the fit is taken as converged.
+/// Usually a handful suffice; the cap bounds the work on data that cycles.
+pub(crate) const ITERATIONS: usize = 50;
+
+/// How far any weight may still move between iterations for the fit to count
+/// as converged.
+pub(crate) const SETTLED: f64 = 1e-6;
+
+/// Weight below which [`Inference`](crate::Inference)'s `Display` lists a
+/// measurement as set aside: it counted for less than half of one at full
+/// weight. The bisquare leaves almost every measurement a little below one, so
+/// listing every weight under one would list them all.
+pub(crate) const SET_ASIDE: f64 = 0.5;
+
+/// Smallest spread of relative residuals the weights are measured against.
+///
+/// A fit that reproduces every measurement but one leaves a spread of rounding
+/// noise, against which the one is infinitely far out — as it should be. With
+/// no floor at all that spread can be exactly zero, and nothing is out at all.
+/// Far above rounding and far below any timing noise.
+const SMALLEST_SPREAD: f64 = 1e-9;
+
+/// Converts the median absolute residual to the standard deviation it implies
+/// under Gaussian noise, `1 / Φ⁻¹(3/4)`.
+const MAD_TO_SIGMA: f64 = 1.4826;
+
+/// The Huber loss' tuning constant: 95% as efficient as least squares on
+/// Gaussian noise.
+const HUBER: f64 = 1.345;
+
+/// The bisquare loss' tuning constant, also at 95% efficiency.
+const BISQUARE: f64 = 4.685;
+
+/// How [`Analysis`](crate::Analysis) weighs each measurement's error when it
+/// fits a model.
+///
+/// The robust losses refit by iteratively reweighted least squares: each
+/// measurement's weight is recomputed from its relative residual under the
+/// previous fit, in units of the residuals' spread, until the weights settle.
+/// A fit's [`relative_error`](crate::Fit::relative_error) is then the root
+/// mean of the loss rather than of the squared error, so an outlier costs
+/// every model a bounded amount instead of deciding between them, and
+/// [`Inference::influence`](crate::Inference::influence) reports the weight
+/// each measurement ended up with.
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, Loss, Model};
+///
+/// let mut data: Vec<(f64, f64)> = (0..8)
+///     .map(|k| 10f64.powf(2.0 + 3.0 * k as f64 / 7.0))
+///     .enumerate()
+///     .map(|(i, n)| (n, (3.0 * n + 50.0) * (1.0 + 0.02 * (i as f64 * 2.3).sin())))
+///     .collect();
+/// // The largest run was descheduled and took three times as long.
+/// data[7].1 *= 3.0;
+///
+/// let fooled = Analysis::new().infer(&data).unwrap();
+/// assert_eq!(fooled.best.model, Model::Linearithmic);
+///
+/// let robust = Analysis::new().loss(Loss::Huber).infer(&data).unwrap();
+/// assert_eq!(robust.best.model, Model::Linear);
+/// assert!(robust.influence[7].weight < 0.5);
+/// ```
+///
+/// Non-exhaustive: a later release may add a loss.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Loss {
+    /// Least squares: every measurement at full weight. Exact and fastest on
+    /// clean data, and steered by any outlier.
+    #[default]
+    Squared,
+
+    /// Squared near the fit and absolute beyond 1.345 standard deviations of
+    /// the residuals, so an outlier's pull grows with its distance but no
+    /// faster. Never ignores a measurement outright, and always converges.
+    Huber,
+
+    /// Tukey's bisquare: measurements more than 4.685 standard deviations out
+    /// get no weight at all, so an outlier stops pulling entirely. Started
+    /// from the Huber fit and held to its spread, since on its own it can
+    /// settle on a poor fit to a few measurements that happen to agree.
+    Bisquare,
+}
+
+/// The weight one measurement carried in the reported fit.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Influence {
+    /// The measurement's input size.
+    pub size: f64,
+
+    /// The measurement's cost, after repeats at its size were collapsed by the
+    /// analysis's [`Aggregation`](crate::Aggregation).
+    pub cost: f64,
+
+    /// Its weight relative to a measurement at full weight, in `[0, 1]`. Zero
+    /// is a measurement the fit ignored as an outlier.
+    pub weight: f64,
+}
+
+impl Loss {
+    /// The losses to reweight under in turn: none for least squares, and
+    /// Huber first for the bisquare.
+    pub(crate) fn stages(self) -> &'static [Loss] {
+        match self {
+            Loss::Squared => &[],
+            Loss::Huber => &[Loss::Huber],
+            Loss::Bisquare => &[Loss::Huber, Loss::Bisquare],
+        }
+    }
+
+    /// Each residual's weight under this loss, given all of them.
+    ///
+    /// Residuals are measured in units of their spread — the median absolute
+    /// residual, scaled to a standard deviation — so the weights do not depend
+    /// on how noisy the benchmark is, only on how far each measurement is from
+    /// the others.
+    pub(crate) fn weights(self, residuals: &[f64]) -> Vec<f64> {
+        match self {
+            Loss::Squared => vec![1.0; residuals.len()],
+            _ => self.weights_at(residuals, spread(residuals)),
+        }
+    }
+
+    /// Each residual's weight with residuals measured in units of `scale`.
+    pub(crate) fn weights_at(self, residuals: &[f64], scale: f64) -> Vec<f64> {
+        residuals
+            .iter()
+            .map(|&residual| match residual.is_finite() {
+                true => self.weight(residual / scale),
+                false => 0.0,
+            })
+            .collect()
+    }
+
+    /// Each residual's contribution to the error a fit under this loss is
+    /// judged on, in the units of a squared residual.
+    ///
+    /// Near the fit that is the squared residual itself. Further out it grows
+    /// as the loss does — linearly under Huber, not at all past the bisquare's
+    /// cutoff — so a measurement set aside still costs the fit a fixed
+    /// penalty. Were it free, a model could score well by setting aside every
+    /// measurement it fails to describe, which is what a robust power law
+    /// does to the small sizes of a quadratic with a constant term.
+    pub(crate) fn penalties(self, residuals: &[f64]) -> Vec<f64> {
+        let scale = match self {
+            Loss::Squared => 1.0,
+            _ => spread(residuals),
+        };
+        residuals
+            .iter()
+            .map(|&residual| scale * scale * self.penalty(residual / scale))
+            .collect()
+    }
+
+    /// The weight of a residual `u` standard deviations from the fit.
+    fn weight(self, u: f64) -> f64 {
+        match self {
+            Loss::Squared => 1.0,
+            Loss::Huber if u.abs() <= HUBER => 1.0,
+            Loss::Huber => HUBER / u.abs(),
+            Loss::Bisquare if u.abs() < BISQUARE => (1.0 - (u / BISQUARE).powi(2)).powi(2),
+            Loss::Bisquare => 0.0,
+        }
+    }
+
+    /// Twice the loss of a residual `u` standard deviations from the fit, so
+    /// that it is `u^2` near zero under every loss.
+    fn penalty(self, u: f64) -> f64 {
+        match self {
+            Loss::Squared => u * u,
+            Loss::Huber if u.abs() <= HUBER => u * u,
+            Loss::Huber => HUBER * (2.0 * u.abs() - HUBER),
+            Loss::Bisquare if u.abs() < BISQUARE => {
+                BISQUARE * BISQUARE / 3.0 * (1.0 - (1.0 - (u / BISQUARE).powi(2)).powi(3))
+            }
+            Loss::Bisquare => BISQUARE * BISQUARE / 3.0,
+        }
+    }
+}
+
+/// The spread of `residuals`, as the standard deviation their median absolute
+/// value implies, and no less than [`SMALLEST_SPREAD`].
+pub(crate) fn spread(residuals: &[f64]) -> f64 {
+    let mut spread: Vec<f64> = residuals.iter().map(|r| r.abs()).collect();
+    linalg::median(&mut spread).map_or(SMALLEST_SPREAD, |mad| {
+        (MAD_TO_SIGMA * mad).max(SMALLEST_SPREAD)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_outlier() -> Vec<f64> {
+        let mut residuals: Vec<f64> = (0..20).map(|i| 0.01 * (i as f64 * 1.7).sin()).collect();
+        residuals[7] = 2.0;
+        residuals
+    }
+
+    #[test]
+    fn least_squares_weighs_everything_fully() {
+        assert!(Loss::Squared
+            .weights(&with_outlier())
+            .iter()
+            .all(|&w| w == 1.0));
+    }
+
+    #[test]
+    fn huber_weighs_an_outlier_down_in_proportion_to_its_distance() {
+        let weights = Loss::Huber.weights(&with_outlier());
+
+        assert!(weights[7] > 0.0 && weights[7] < 0.01);
+        assert!(weights.iter().filter(|&&w| w == 1.0).count() >= 15);
+    }
+
+    #[test]
+    fn bisquare_ignores_an_outlier() {
+        let weights = Loss::Bisquare.weights(&with_outlier());
+
+        assert_eq!(weights[7], 0.0);
+        assert!(weights.iter().enumerate().all(|(i, &w)| i == 7 || w > 0.5));
+    }
+
+    #[test]
+    fn penalties_match_squares_near_the_fit_and_grow_slower_beyond() {
+        let residuals = with_outlier();
+        let squares: Vec<f64> = residuals.iter().map(|r| r * r).collect();
+
+        assert_eq!(Loss::Squared.penalties(&residuals), squares);
+        for loss in [Loss::Huber, Loss::Bisquare] {
+            let penalties = loss.penalties(&residuals);
+            assert!((penalties[1] / squares[1] - 1.0).abs() < 0.05, "{loss:?}");
+            assert!(penalties[7] < 0.05 * squares[7], "{loss:?}");
+        }
+    }
+
+    #[test]
+    fn rounding_noise_is_not_an_outlier() {
+        let residuals: Vec<f64> = (0..10).map(|i| 1e-15 * (i as f64).sin()).collect();
+
+        assert!(Loss::Bisquare
+            .weights(&residuals)
+            .iter()
+            .all(|&w| w > 0.999));
+    }
+
+    #[test]
+    fn one_miss_among_exact_measurements_is_ignored() {
+        let mut residuals = vec![0.0; 10];
+        residuals[3] = 0.5;
+
+        let weights = Loss::Bisquare.weights(&residuals);
+
+        assert_eq!(weights[3], 0.0);
+        assert!(weights.iter().filter(|&&w| w == 1.0).count() == 9);
+    }
+}
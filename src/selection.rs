@@ -0,0 +1,232 @@
+//! Rules for choosing the model to report among those that were fitted.
+//!
+//! The default rule prefers the simplest model within a margin of the best
+//! score, with margins set by the crate's own accuracy sweep. The information
+//! criteria are the textbook alternative: each trades fit against parameters by
+//! a formula with a derivation behind it, so a report can name the rule it used
+//! and a reader can check it.
+
+use crate::model::Model;
+use crate::validation::Validation;
+
+/// Smallest relative error the information criteria take the logarithm of.
+///
+/// An exact fit has an error of zero and a log-likelihood of infinity, and two
+/// exact fits would then be ranked by floating point noise. Held here, every
+/// fit that is exact to working precision scores the same likelihood, and the
+/// parameter penalty alone separates them — which is the right answer: the
+/// simpler of two exact models.
+pub(crate) const SMALLEST_RELATIVE_ERROR: f64 = 1e-8;
+
+/// How [`Analysis`](crate::Analysis) chooses the model to report.
+///
+/// The three information criteria rank fits by `-2 ln L + penalty`, where `L`
+/// is the likelihood of the measurements under Gaussian noise proportional to
+/// the cost — the same relative residuals every fit is scored on — and the
+/// penalty grows with the free parameters the fit spent, counting the noise
+/// scale as one of them. The lowest value wins. Each fit's value and its
+/// weight of evidence are reported in [`Inference::criteria`](crate::Inference::criteria).
+///
+/// What the criteria will not do is prefer a named model to a free exponent
+/// that fits it as closely with as many parameters. On quadratic data with 5%
+/// of timing noise they report `O(n^2.01)` rather than `O(n^2)` about one time
+/// in four, where [`Tolerance`](Selection::Tolerance) reports `O(n^2)`. The
+/// fitted exponent is right; it is just not the name.
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, Model, Selection};
+///
+/// let data: Vec<(f64, f64)> = (0..24)
+///     .map(|k| 10f64.powf(k as f64 / 6.0))
+///     .map(|n| (n, 3.0 * n * n + 40.0))
+///     .collect();
+///
+/// let inference = Analysis::new()
+///     .selection(Selection::Bic)
+///     .infer(&data)
+///     .unwrap();
+///
+/// assert_eq!(inference.best.model, Model::Quadratic);
+/// assert!(inference.criteria[0].weight > 0.9);
+/// ```
+///
+/// Non-exhaustive: a later release may add a rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Selection {
+    /// The simplest model whose corrected error is within a tolerance of the
+    /// best one's. Tuned on the crate's accuracy sweep, where it recovers
+    /// every default model from noisy data at least 95% of the time.
+    #[default]
+    Tolerance,
+
+    /// Akaike's information criterion, `n ln(e^2) + 2k`: the fit expected to
+    /// predict new measurements best. Lenient with parameters on small samples.
+    Aic,
+
+    /// AIC with the small-sample correction `2k(k + 1) / (n - k - 1)`, which is
+    /// what AIC should be on samples of a few dozen sizes. The correction is
+    /// infinite for a fit with as many parameters as the sample has points
+    /// less two, so such a fit ranks last.
+    Aicc,
+
+    /// The Bayesian information criterion, `n ln(e^2) + k ln n`: the fit most
+    /// likely to be the true model, if one of them is. Penalizes parameters
+    /// harder than AIC once there are eight or more points.
+    Bic,
+
+    /// The lowest [`Fit::validation_error`](crate::Fit::validation_error):
+    /// the fit that best predicts measurements it was not fitted to, under the
+    /// given split. No parameter is counted, because none needs to be —
+    /// freedom a model spent on the noise shows up as error on the points held
+    /// out. Costs one refit per fold per model, in every confidence resample.
+    ///
+    /// Also the rule least inclined to simplicity: with 5% of timing noise it
+    /// reports flat data as a curve of negligible gain about one time in three.
+    CrossValidation(Validation),
+}
+
+/// One fit's standing under an information criterion.
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Criterion {
+    /// The model the fit belongs to.
+    pub model: Model,
+
+    /// The criterion's value. Only differences between fits mean anything,
+    /// and lower is better.
+    pub value: f64,
+
+    /// The Akaike weight: `exp(-Δ/2)`, normalized over every fit, where `Δ`
+    /// is the fit's distance from the lowest value. Read as the probability
+    /// that this fit is the best of those compared, in `[0, 1]`.
+    pub weight: f64,
+}
+
+impl Selection {
+    /// The criterion used to rank fits and weigh them in
+    /// [`Inference::criteria`](crate::Inference::criteria).
+    ///
+    /// The rules that rank by something else weigh their fits by AICc, the
+    /// criterion meant for samples as small as benchmarks take.
+    pub(crate) fn criterion(self) -> Selection {
+        match self {
+            Selection::Tolerance | Selection::CrossValidation(_) => Selection::Aicc,
+            other => other,
+        }
+    }
+
+    /// The value of this criterion for a fit with `relative_error`, reached by
+    /// spending `parameters` free parameters on `points` points.
+    ///
+    /// The measurements' own log-likelihood terms, which every fit shares, are
+    /// left out: they shift each value by the same amount.
+    pub(crate) fn value(self, relative_error: f64, parameters: usize, points: usize) -> f64 {
+        let n = points as f64;
+        // The noise scale is estimated too.
+        let k = (parameters + 1) as f64;
+        let fit = n * relative_error.max(SMALLEST_RELATIVE_ERROR).powi(2).ln();
+
+        match self.criterion() {
+            Selection::Bic => fit + k * n.ln(),
+            Selection::Aicc if n - k - 1.0 > 0.0 => {
+                fit + 2.0 * k + 2.0 * k * (k + 1.0) / (n - k - 1.0)
+            }
+            Selection::Aicc => f64::INFINITY,
+            _ => fit + 2.0 * k,
+        }
+    }
+}
+
+/// Pairs each model with its criterion value and its Akaike weight.
+pub(crate) fn weigh(values: impl IntoIterator<Item = (Model, f64)>) -> Vec<Criterion> {
+    let values: Vec<(Model, f64)> = values.into_iter().collect();
+    let lowest = values
+        .iter()
+        .map(|&(_, value)| value)
+        .fold(f64::INFINITY, f64::min);
+    let relative = |value: f64| match lowest.is_finite() {
+        true => (-(value - lowest) / 2.0).exp(),
+        false => 0.0,
+    };
+    let total: f64 = values.iter().map(|&(_, value)| relative(value)).sum();
+
+    values
+        .into_iter()
+        .map(|(model, value)| Criterion {
+            model,
+            value,
+            weight: match total > 0.0 {
+                true => relative(value) / total,
+                false => 0.0,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_parameter_costs_two_under_aic_and_ln_n_under_bic() {
+        let aic = Selection::Aic.value(0.1, 3, 20) - Selection::Aic.value(0.1, 2, 20);
+        let bic = Selection::Bic.value(0.1, 3, 20) - Selection::Bic.value(0.1, 2, 20);
+
+        assert!((aic - 2.0).abs() < 1e-12);
+        assert!((bic - 20f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn the_small_sample_correction_vanishes_as_the_sample_grows() {
+        let gap =
+            |points| Selection::Aicc.value(0.1, 2, points) - Selection::Aic.value(0.1, 2, points);
+
+        assert!(gap(6) > gap(24));
+        assert!(gap(10_000) < 1e-2);
+        assert_eq!(Selection::Aicc.value(0.1, 2, 4), f64::INFINITY);
+    }
+
+    #[test]
+    fn exact_fits_are_separated_by_their_parameters_alone() {
+        let constant = Selection::Bic.value(0.0, 1, 12);
+        let line = Selection::Bic.value(1e-15, 2, 12);
+
+        assert!(constant < line);
+        assert!((line - constant - 12f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weights_sum_to_one_and_follow_the_values() {
+        let criteria = weigh([
+            (Model::Linear, 10.0),
+            (Model::Quadratic, 12.0),
+            (Model::Cubic, 30.0),
+        ]);
+        let total: f64 = criteria.iter().map(|c| c.weight).sum();
+
+        assert!((total - 1.0).abs() < 1e-12);
+        assert!((criteria[0].weight / criteria[1].weight - 1f64.exp()).abs() < 1e-9);
+        assert!(criteria[2].weight < 1e-4);
+    }
+
+    #[test]
+    fn weighs_nothing_when_no_value_is_finite() {
+        let criteria = weigh([(Model::Linear, f64::INFINITY)]);
+
+        assert_eq!(criteria[0].weight, 0.0);
+    }
+
+    #[test]
+    fn tolerance_is_weighed_by_aicc() {
+        assert_eq!(
+            Selection::Tolerance.value(0.1, 2, 12),
+            Selection::Aicc.value(0.1, 2, 12)
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Shapes defined outside the crate, fitted and compared like the built-in
+//! models.
+//!
+//! The built-in models are the complexities most code has. Some code has one
+//! that is not among them — `O(n log log n)` for a van Emde Boas tree, the
+//! inverse Ackermann function for union-find — and reporting it as the nearest
+//! built-in model is exactly the misreading the named models exist to prevent.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A candidate complexity supplied by the caller.
+///
+/// A shape is fitted the way the built-in models are: each measurement is
+/// transformed into a space where the shape is the straight line
+/// `gain * u + offset`, the line is fitted by least squares, and the result is
+/// judged in the original space by the same relative error as every other
+/// candidate. Pass one to [`Analysis::shape`](crate::Analysis::shape), or name
+/// it as [`Model::Custom`](crate::Model::Custom) anywhere a model is taken.
+///
+/// Shapes are identified by their notation. Two shapes with the same notation
+/// are the same model as far as the crate is concerned — in confidence
+/// resampling, in [`Inference::all`](crate::Inference::all), and in equality.
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, Model, Shape};
+///
+/// /// `O(n log log n)`.
+/// #[derive(Debug)]
+/// struct LogLog;
+///
+/// impl Shape for LogLog {
+///     fn notation(&self) -> &'static str {
+///         "O(n log log n)"
+///     }
+///
+///     fn degree(&self) -> f64 {
+///         1.05
+///     }
+///
+///     fn transform(&self, x: f64, cost: f64) -> Option<(f64, f64)> {
+///         let u = x * x.ln().ln();
+///         u.is_finite().then_some((u, cost))
+///     }
+///
+///     fn inverse(&self, gain: f64, offset: f64, x: f64) -> f64 {
+///         gain * x * x.ln().ln() + offset
+///     }
+/// }
+///
+/// let data: Vec<(f64, f64)> = (1..=24)
+///     .map(|k| 10f64.powf(0.5 + k as f64 / 6.0))
+///     .map(|n| (n, 3.0 * n * n.ln().ln() + 50.0))
+///     .collect();
+///
+/// let inference = Analysis::new().shape(&LogLog).infer(&data).unwrap();
+///
+/// assert_eq!(inference.best.model, Model::Custom(&LogLog));
+/// assert_eq!(inference.best.to_string(), "O(n log log n)");
+/// assert!(inference.best.is_at_most(Model::Linearithmic));
+/// ```
+pub trait Shape: fmt::Debug + Send + Sync {
+    /// How the shape is written, as in `O(n log log n)`. Also what identifies
+    /// it, so it should be unique among the shapes in one analysis.
+    fn notation(&self) -> &'static str;
+
+    /// Where the shape sits on the polynomial-degree scale, which is what
+    /// [`Fit::is_at_most`](crate::Fit::is_at_most) and its relatives compare.
+    ///
+    /// `O(n^a)` sits at `a`, and a factor of `log n` adds `0.13` — the power of
+    /// `n` that grows by as much over the input sizes benchmarks reach. So
+    /// `O(n log log n)` belongs a little above `1` and below `1.13`.
+    fn degree(&self) -> f64;
+
+    /// Transforms a measured `(input size, cost)` into the space where the
+    /// shape is a straight line, or `None` if the point has no image there.
+    ///
+    /// A point without an image is left out of this shape's fit alone, as a
+    /// logarithmic model leaves out an input size of zero.
+    fn transform(&self, x: f64, cost: f64) -> Option<(f64, f64)>;
+
+    /// The cost the shape predicts at `x`, given the `gain` and `offset` of the
+    /// line fitted in transformed space. Undoes [`transform`](Self::transform).
+    fn inverse(&self, gain: f64, offset: f64, x: f64) -> f64;
+
+    /// Free parameters the shape estimates from the data, for the correction
+    /// that keeps a more flexible model from winning by fitting the noise.
+    ///
+    /// Two, the gain and the offset, unless the transform itself was tuned to
+    /// the measurements.
+    fn parameters(&self) -> usize {
+        2
+    }
+
+    /// Whether [`transform`](Self::transform) takes the logarithm of the cost,
+    /// as a free-exponent power law does.
+    ///
+    /// A residual in log-cost space is already relative, so such a fit is not
+    /// weighted again; a fit against the cost itself is weighted to minimize
+    /// the relative error. Defaults to `false`.
+    fn log_cost(&self) -> bool {
+        false
+    }
+}
+
+/// Shapes are the same shape when they are written the same way.
+impl PartialEq for dyn Shape {
+    fn eq(&self, other: &Self) -> bool {
+        self.notation() == other.notation()
+    }
+}
+
+impl Eq for dyn Shape {}
+
+impl Hash for dyn Shape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.notation().hash(state);
+    }
+}
+
+/// Writes a shape as its notation: the closest thing to a name it has, and
+/// enough for a stored inference to be read by a person. Reading one back
+/// would need the shape's code, so a shape is never deserialized.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_notation<S: serde::Serializer>(
+    shape: &&'static dyn Shape,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(shape.notation())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Analysis;
+    use crate::fit::ModelParams;
+    use crate::model::Model;
+
+    #[derive(Debug)]
+    struct LogLog;
+
+    impl Shape for LogLog {
+        fn notation(&self) -> &'static str {
+            "O(n log log n)"
+        }
+
+        fn degree(&self) -> f64 {
+            1.05
+        }
+
+        fn transform(&self, x: f64, cost: f64) -> Option<(f64, f64)> {
+            let u = x * x.ln().ln();
+            u.is_finite().then_some((u, cost))
+        }
+
+        fn inverse(&self, gain: f64, offset: f64, x: f64) -> f64 {
+            gain * x * x.ln().ln() + offset
+        }
+    }
+
+    /// The same notation under another type.
+    #[derive(Debug)]
+    struct Impostor;
+
+    impl Shape for Impostor {
+        fn notation(&self) -> &'static str {
+            "O(n log log n)"
+        }
+
+        fn degree(&self) -> f64 {
+            0.0
+        }
+
+        fn transform(&self, _: f64, _: f64) -> Option<(f64, f64)> {
+            None
+        }
+
+        fn inverse(&self, _: f64, _: f64, _: f64) -> f64 {
+            0.0
+        }
+    }
+
+    fn log_log(gain: f64, offset: f64) -> Vec<(f64, f64)> {
+        (1..=24)
+            .map(|k| 10f64.powf(0.5 + k as f64 / 6.0))
+            .map(|n| (n, gain * n * n.ln().ln() + offset))
+            .collect()
+    }
+
+    #[test]
+    fn a_custom_shape_competes_with_the_built_in_models() {
+        let inference = Analysis::new()
+            .shape(&LogLog)
+            .infer(log_log(3.0, 50.0))
+            .unwrap();
+
+        assert_eq!(inference.best.model, Model::Custom(&LogLog));
+        assert!(inference.confidence > 0.9);
+        assert!(inference.all.iter().any(|fit| fit.model == Model::Linear));
+        match inference.best.params {
+            ModelParams::Custom { gain, offset, .. } => {
+                assert!((gain - 3.0).abs() < 1e-6);
+                assert!((offset - 50.0).abs() < 1e-3);
+            }
+            other => panic!("expected custom parameters, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn loses_to_a_built_in_model_that_fits_better() {
+        let data: Vec<(f64, f64)> = (1..=24)
+            .map(|k| 10f64.powf(k as f64 / 6.0))
+            .map(|n| (n, n * n))
+            .collect();
+
+        let inference = Analysis::new().shape(&LogLog).infer(&data).unwrap();
+
+        assert_eq!(inference.best.model, Model::Quadratic);
+        assert!(inference
+            .all
+            .iter()
+            .any(|fit| fit.model == Model::Custom(&LogLog)));
+    }
+
+    #[test]
+    fn places_itself_on_the_degree_scale() {
+        let fit = Analysis::new()
+            .models([Model::Custom(&LogLog)])
+            .infer(log_log(1.0, 0.0))
+            .unwrap()
+            .best;
+
+        assert!(fit.is_faster_than(Model::Linearithmic));
+        assert!(!fit.is_at_most(Model::Linear));
+        assert_eq!(fit.evaluate(1e6), LogLog.inverse(1.0, 0.0, 1e6));
+    }
+
+    #[test]
+    fn is_identified_by_its_notation() {
+        assert_eq!(Model::Custom(&LogLog), Model::Custom(&Impostor));
+        assert_ne!(Model::Custom(&LogLog), Model::Linear);
+        assert_eq!(Model::Custom(&LogLog).to_string(), "O(n log log n)");
+    }
+
+    #[test]
+    fn opting_into_polylog_keeps_a_custom_shape() {
+        let inference = Analysis::new()
+            .shape(&LogLog)
+            .polylog()
+            .infer(log_log(3.0, 50.0))
+            .unwrap();
+
+        assert_eq!(inference.best.model, Model::Custom(&LogLog));
+    }
+
+    #[test]
+    fn adding_a_shape_twice_fits_it_once() {
+        let inference = Analysis::new()
+            .shape(&LogLog)
+            .shape(&LogLog)
+            .infer(log_log(3.0, 50.0))
+            .unwrap();
+
+        let custom = inference
+            .all
+            .iter()
+            .filter(|fit| fit.model == Model::Custom(&LogLog))
+            .count();
+        assert_eq!(custom, 1);
+    }
+}
@@ -0,0 +1,187 @@
+//! How far each fitted coefficient could plausibly be from the value reported.
+//!
+//! A fitted `O(n^1.48)` is a point estimate. Whether `1.5` — or `1` — is also
+//! consistent with the measurements depends on how much the exponent moves when
+//! the measurements do, which is what refitting resamples of them shows.
+
+use crate::fit::{Fit, ModelParams};
+use crate::linalg;
+
+/// Fewest refits an interval is drawn from. Below this a percentile is one of
+/// a handful of values, and says more about the draw than the data.
+const MIN_REFITS: usize = 10;
+
+/// The spread of a fit's coefficients across bootstrap resamples of the
+/// measurements.
+///
+/// Each field is shaped like the fit's own [`ModelParams`] — the same variant,
+/// with every coefficient replaced by its standard error or a bound on it — so
+/// the uncertainty of an exponent is read from the same field as the exponent.
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, ModelParams};
+///
+/// let data: Vec<(f64, f64)> = (1..=24)
+///     .map(|k| 10f64.powf(k as f64 / 6.0))
+///     .enumerate()
+///     .map(|(i, n)| (n, n.powf(1.48) * (1.0 + 0.2 * (i as f64 * 2.7).sin())))
+///     .collect();
+///
+/// let inference = Analysis::new().uncertainty(0.95).infer(&data).unwrap();
+/// let uncertainty = inference.best.uncertainty.unwrap();
+///
+/// // Superlinear, and certainly so: the whole interval lies above one.
+/// match uncertainty.lower {
+///     ModelParams::Polynomial { power, .. } => assert!(power > 1.0),
+///     other => panic!("expected a power law, got {other:?}"),
+/// }
+/// assert_eq!(format!("{:#}", inference.best), "O(n^1.48 ± 0.02)");
+/// ```
+///
+/// Non-exhaustive: read the fields, but let the crate build the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Uncertainty {
+    /// The probability each interval was drawn to cover, as passed to
+    /// [`Analysis::uncertainty`](crate::Analysis::uncertainty).
+    pub level: f64,
+
+    /// Each coefficient's standard error: its standard deviation across the
+    /// resamples.
+    pub standard_error: ModelParams,
+
+    /// Each coefficient's lower bound: the `(1 - level) / 2` percentile of its
+    /// values across the resamples.
+    pub lower: ModelParams,
+
+    /// Each coefficient's upper bound: the `(1 + level) / 2` percentile.
+    pub upper: ModelParams,
+}
+
+impl Uncertainty {
+    /// Half the width of the interval on the exponent of `O(n^m)` or the base
+    /// of `O(c^n)`, which is what the alternate `Display` of a [`Fit`] shows
+    /// after `±`. `None` for the models with no such parameter.
+    pub(crate) fn margin(&self) -> Option<f64> {
+        match (self.lower, self.upper) {
+            (
+                ModelParams::Polynomial { power: lower, .. },
+                ModelParams::Polynomial { power: upper, .. },
+            )
+            | (
+                ModelParams::Exponential { base: lower, .. },
+                ModelParams::Exponential { base: upper, .. },
+            ) => Some((upper - lower) / 2.0),
+            _ => None,
+        }
+    }
+}
+
+/// Summarizes the coefficients of `refits` — the fit's model refitted to
+/// resamples, `None` where a resample could not be fitted — as intervals at
+/// `level`.
+///
+/// `None` when fewer than [`MIN_REFITS`] resamples could be fitted.
+pub(crate) fn summarize(fit: &Fit, level: f64, refits: &[Option<Fit>]) -> Option<Uncertainty> {
+    let samples: Vec<Vec<f64>> = refits
+        .iter()
+        .flatten()
+        .map(|refit| refit.params.coefficients())
+        .collect();
+    if samples.len() < MIN_REFITS {
+        return None;
+    }
+    let mut columns: Vec<Vec<f64>> = (0..fit.params.coefficients().len())
+        .map(|i| {
+            samples
+                .iter()
+                .filter_map(|row| row.get(i).copied())
+                .collect()
+        })
+        .collect();
+
+    let tail = (1.0 - level) / 2.0;
+    let mut summary = |statistic: &dyn Fn(&mut [f64]) -> Option<f64>| {
+        let mut complete = true;
+        let params =
+            fit.params.map(
+                |i, _| match columns.get_mut(i).and_then(|column| statistic(column)) {
+                    Some(value) => value,
+                    None => {
+                        complete = false;
+                        f64::NAN
+                    }
+                },
+            );
+        complete.then_some(params)
+    };
+
+    Some(Uncertainty {
+        level,
+        standard_error: summary(&|column| linalg::standard_deviation(column))?,
+        lower: summary(&|column| linalg::quantile(column, tail))?,
+        upper: summary(&|column| linalg::quantile(column, 1.0 - tail))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::fit_of;
+    use crate::model::Model;
+
+    fn linear(gain: f64, offset: f64) -> Fit {
+        fit_of(Model::Linear, ModelParams::Linear { gain, offset }, 0.0)
+    }
+
+    #[test]
+    fn bounds_each_coefficient_by_its_own_percentiles() {
+        let refits: Vec<Option<Fit>> = (0..=100)
+            .map(|i| Some(linear(i as f64, 1000.0 - i as f64)))
+            .collect();
+
+        let uncertainty = summarize(&linear(50.0, 950.0), 0.5, &refits).unwrap();
+
+        assert_eq!(
+            uncertainty.lower,
+            ModelParams::Linear {
+                gain: 25.0,
+                offset: 925.0
+            }
+        );
+        assert_eq!(
+            uncertainty.upper,
+            ModelParams::Linear {
+                gain: 75.0,
+                offset: 975.0
+            }
+        );
+        match uncertainty.standard_error {
+            ModelParams::Linear { gain, offset } => {
+                assert!((gain - offset).abs() < 1e-9);
+                assert!((gain - 29.3).abs() < 0.1, "got {gain}");
+            }
+            other => panic!("expected a line, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn skips_resamples_that_could_not_be_fitted() {
+        let mut refits: Vec<Option<Fit>> = vec![None; 50];
+        refits.extend((0..MIN_REFITS).map(|_| Some(linear(2.0, 3.0))));
+
+        let uncertainty = summarize(&linear(2.0, 3.0), 0.95, &refits).unwrap();
+
+        assert_eq!(uncertainty.lower, uncertainty.upper);
+        assert_eq!(uncertainty.margin(), None, "a line has no exponent");
+    }
+
+    #[test]
+    fn draws_no_interval_from_too_few_refits() {
+        let refits = vec![Some(linear(1.0, 1.0)); MIN_REFITS - 1];
+
+        assert_eq!(summarize(&linear(1.0, 1.0), 0.95, &refits), None);
+    }
+}
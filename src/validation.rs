@@ -0,0 +1,165 @@
+//! Out-of-sample error: how well a model fitted without some measurements
+//! predicts them.
+//!
+//! Every score a fit carries is in-sample, so a model with more freedom always
+//! looks at least as good as a simpler one, and selection has to correct for
+//! that by rule. Held-out error needs no correction: freedom spent on the noise
+//! of the measurements a fit saw is paid for at the ones it did not.
+
+use crate::data::{self, Sample};
+use crate::fit::{self, Fit};
+
+/// How the sample is split to measure a fit's out-of-sample error, reported as
+/// [`Fit::validation_error`].
+///
+/// # Example
+/// ```
+/// use big_o::{Analysis, Model, Selection, Validation};
+///
+/// let data: Vec<(f64, f64)> = (0..24)
+///     .map(|k| 10f64.powf(k as f64 / 6.0))
+///     .map(|n| (n, 3.0 * n * n.ln() + 40.0))
+///     .collect();
+///
+/// let inference = Analysis::new()
+///     .selection(Selection::CrossValidation(Validation::KFold(4)))
+///     .infer(&data)
+///     .unwrap();
+///
+/// assert_eq!(inference.best.model, Model::Linearithmic);
+/// assert!(inference.best.validation_error.unwrap() < 1e-6);
+/// ```
+///
+/// Non-exhaustive: a later release may split the sample another way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Validation {
+    /// Refits once per measured input size, without it, and predicts it. The
+    /// most thorough split, and the slowest: one fit per point per model.
+    LeaveOneOut,
+
+    /// Deals the input sizes, in ascending order, into this many folds, and
+    /// refits once per fold without it. Adjacent sizes land in different folds,
+    /// so every fold spans the whole range. Fewer than two folds, or more folds
+    /// than points, is taken as [`LeaveOneOut`](Self::LeaveOneOut).
+    KFold(usize),
+}
+
+impl Validation {
+    /// How many folds this split makes of `points` points.
+    fn folds(self, points: usize) -> usize {
+        match self {
+            Validation::KFold(folds) if (2..=points).contains(&folds) => folds,
+            _ => points,
+        }
+    }
+}
+
+/// The relative error of `refit`'s predictions for the points it was not
+/// shown, pooled over every fold.
+///
+/// `refit` fits the model under validation to a training sample, so a fit the
+/// analysis refines is validated refined. `None` when some fold leaves too few
+/// points to fit, or the model cannot be fitted to one.
+pub(crate) fn validation_error(
+    sample: &Sample,
+    validation: Validation,
+    refit: impl Fn(&Sample) -> Option<Fit>,
+) -> Option<f64> {
+    let points = sample.points();
+    let folds = validation.folds(points.len());
+
+    let mut predicted = Vec::with_capacity(points.len());
+    for fold in 0..folds {
+        let training = sample.select((0..points.len()).filter(|i| i % folds != fold));
+        if training.points().len() < data::MIN_POINTS {
+            return None;
+        }
+        let fitted = refit(&training)?;
+
+        predicted.extend(
+            points
+                .iter()
+                .skip(fold)
+                .step_by(folds)
+                .map(|&(x, y)| (y, fitted.evaluate(x))),
+        );
+    }
+    fit::score_predictions(&predicted).map(|(_, relative_error)| relative_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregation::Aggregation;
+    use crate::model::Model;
+    use crate::robust::Loss;
+
+    fn validated(model: Model, data: &[(f64, f64)], validation: Validation) -> Option<f64> {
+        validation_error(
+            &data::prepare(data, Aggregation::Median, None),
+            validation,
+            |training| fit::fit(model, training, Loss::Squared),
+        )
+    }
+
+    fn quadratic(noise: f64) -> Vec<(f64, f64)> {
+        (1..=12)
+            .map(|n| n as f64)
+            .map(|n| (n, n * n * (1.0 + noise * (n * 7.3).sin())))
+            .collect()
+    }
+
+    #[test]
+    fn a_model_that_generated_the_data_predicts_it_exactly() {
+        for validation in [Validation::LeaveOneOut, Validation::KFold(3)] {
+            let error = validated(Model::Quadratic, &quadratic(0.0), validation).unwrap();
+
+            assert!(error < 1e-9, "{validation:?}: {error}");
+        }
+    }
+
+    #[test]
+    fn held_out_error_exceeds_in_sample_error() {
+        let data = quadratic(0.05);
+        let in_sample = fit::fit(
+            Model::Quadratic,
+            &data::prepare(&data, Aggregation::Median, None),
+            Loss::Squared,
+        )
+        .unwrap()
+        .relative_error;
+        let held_out = validated(Model::Quadratic, &data, Validation::LeaveOneOut).unwrap();
+
+        assert!(held_out > in_sample);
+    }
+
+    #[test]
+    fn a_wrong_model_predicts_worse_than_the_right_one() {
+        let data = quadratic(0.02);
+
+        let right = validated(Model::Quadratic, &data, Validation::KFold(4)).unwrap();
+        let wrong = validated(Model::Linear, &data, Validation::KFold(4)).unwrap();
+
+        assert!(wrong > 5.0 * right);
+    }
+
+    #[test]
+    fn too_many_or_too_few_folds_are_leave_one_out() {
+        assert_eq!(Validation::KFold(1).folds(10), 10);
+        assert_eq!(Validation::KFold(50).folds(10), 10);
+        assert_eq!(Validation::KFold(5).folds(10), 5);
+        assert_eq!(Validation::LeaveOneOut.folds(10), 10);
+    }
+
+    #[test]
+    fn a_sample_too_small_to_hold_out_from_is_not_validated() {
+        let data = [(1., 1.), (2., 4.), (3., 9.)];
+
+        assert_eq!(
+            validated(Model::Quadratic, &data, Validation::LeaveOneOut),
+            None
+        );
+    }
+}
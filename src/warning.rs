@@ -49,6 +49,56 @@ pub enum Warning {
     /// comparison — typically the log-space models given an input size of
     /// zero, or a cost of zero.
     ModelsSkipped(Vec<Model>),
+
+    /// Two input sizes were measured rising together, so their effects on the
+    /// cost cannot be told apart: `O(V)`, `O(E)` and `O(V + E)` describe data
+    /// where `E` is always `2V` equally well.
+    ///
+    /// Raised only by [`MultiAnalysis`](crate::MultiAnalysis). Measuring sizes
+    /// that vary independently — a grid, or each one swept with the others
+    /// held still — is the remedy.
+    CorrelatedSizes {
+        /// Name of the first of the two sizes.
+        first: String,
+        /// Name of the second.
+        second: String,
+        /// Correlation of their logarithms across the sample, in `[-1, 1]`.
+        correlation: f64,
+    },
+
+    /// The cost follows different curves over different ranges of input size,
+    /// and fitting each range on its own explains the measurements decisively
+    /// better than one fit across them all.
+    ///
+    /// The single fit averages the regimes — `O(n)` with a cache cliff reads
+    /// as `O(n^1.3)` — so it describes none of them.
+    /// [`Analysis::regimes`](crate::Analysis::regimes) infers each one.
+    Piecewise {
+        /// The input sizes the regimes change at, ascending, each midway in
+        /// log space between the last size measured in one regime and the
+        /// first measured in the next.
+        breakpoints: Vec<f64>,
+    },
+
+    /// Measurements were rejected as outliers before fitting, as
+    /// [`Analysis::outliers`](crate::Analysis::outliers) asked. The fit
+    /// describes the rest; whether the rejected ones were noise or the
+    /// routine's own behaviour is for the caller to judge.
+    OutliersRemoved {
+        /// How many measurements were rejected, counting each repeat.
+        count: usize,
+        /// The distinct input sizes they were measured at, ascending.
+        sizes: Vec<f64>,
+    },
+
+    /// Measurements had a size or cost that no `f64` holds exactly — a count
+    /// past 2^53 — and were fitted rounded to the nearest one. Harmless for a
+    /// fit, which no rounding at that scale moves, but a sign that the
+    /// measurements carry more precision than the analysis looked at.
+    LossyConversion {
+        /// How many measurements were rounded.
+        count: usize,
+    },
 }
 
 impl fmt::Display for Warning {
@@ -74,6 +124,38 @@ impl fmt::Display for Warning {
                 }
                 Ok(())
             }
+            Warning::CorrelatedSizes {
+                first,
+                second,
+                correlation,
+            } => write!(
+                f,
+                "sizes {first} and {second} move together (correlation {correlation:.2}), so their effects cannot be separated"
+            ),
+            Warning::Piecewise { breakpoints } => {
+                write!(f, "cost changes regime at input size")?;
+                for (i, at) in breakpoints.iter().enumerate() {
+                    match i {
+                        0 => write!(f, " {at:.0}")?,
+                        _ => write!(f, ", {at:.0}")?,
+                    }
+                }
+                write!(f, "; one fit across them describes none of them")
+            }
+            Warning::OutliersRemoved { count, sizes } => {
+                write!(f, "{count} measurements removed as outliers, at input size")?;
+                for (i, at) in sizes.iter().enumerate() {
+                    match i {
+                        0 => write!(f, " {at:.0}")?,
+                        _ => write!(f, ", {at:.0}")?,
+                    }
+                }
+                Ok(())
+            }
+            Warning::LossyConversion { count } => write!(
+                f,
+                "{count} measurements rounded to the nearest float, their size or cost too large to convert exactly"
+            ),
         }
     }
 }
@@ -100,5 +182,33 @@ mod tests {
             Warning::ModelsSkipped(vec![Model::Logarithmic, Model::Polynomial]).to_string(),
             "models that could not consume the data: O(log n), O(n^m)"
         );
+        assert_eq!(
+            Warning::CorrelatedSizes {
+                first: "V".into(),
+                second: "E".into(),
+                correlation: 0.994
+            }
+            .to_string(),
+            "sizes V and E move together (correlation 0.99), so their effects cannot be separated"
+        );
+        assert_eq!(
+            Warning::Piecewise {
+                breakpoints: vec![4_870.2, 210_000.0]
+            }
+            .to_string(),
+            "cost changes regime at input size 4870, 210000; one fit across them describes none of them"
+        );
+        assert_eq!(
+            Warning::OutliersRemoved {
+                count: 3,
+                sizes: vec![100.0, 6_400.0]
+            }
+            .to_string(),
+            "3 measurements removed as outliers, at input size 100, 6400"
+        );
+        assert_eq!(
+            Warning::LossyConversion { count: 2 }.to_string(),
+            "2 measurements rounded to the nearest float, their size or cost too large to convert exactly"
+        );
     }
 }
@@ -7,7 +7,7 @@
 
 mod synthetic;
 
-use big_o::Model;
+use big_o::{Analysis, Model, Selection, Validation};
 use std::collections::BTreeMap;
 
 /// Trials per model at each noise level in the checked-in sweep.
@@ -28,16 +28,29 @@ const NOISE: f64 = 0.05;
 /// completely behind seven that succeed.
 const REQUIRED: f64 = 0.95;
 
-/// Runs `trials` per model at noise level `sigma` and returns, per model, the
-/// fraction of trials that recovered it.
+/// Fraction of trials the compound models of [`Analysis::polylog`] must recover,
+/// with every model competing.
+///
+/// Lower than [`REQUIRED`], and by measurement: one logarithmic factor is what
+/// separates `O(n^2 log n)` from `O(n^2)`, and a constant term worth a fifth of
+/// the largest measurement hides most of the range it shows in. At 5% noise
+/// each of that pair is recovered about nine times in ten. This is why the
+/// compound models are opt-in, and the bar keeps opting in from getting worse.
+const POLYLOG_REQUIRED: f64 = 0.85;
+
 /// Runs `trials` per model at noise level `sigma`, over every constant-term
 /// share, and returns per model the fraction of trials that recovered it.
 ///
 /// Every share counts towards one rate per model rather than one rate per
 /// share, so a model that is only recovered under the easier shape cannot pass.
-fn recovery_rates(trials: u64, sigma: f64) -> BTreeMap<&'static str, (f64, Vec<String>)> {
+fn recovery_rates(
+    models: &[Model],
+    analysis: &Analysis,
+    trials: u64,
+    sigma: f64,
+) -> BTreeMap<&'static str, (f64, Vec<String>)> {
     let mut rates = BTreeMap::new();
-    for model in MODELS {
+    for &model in models {
         let mut recovered = 0u64;
         let mut attempted = 0u64;
         let mut missed: Vec<String> = Vec::new();
@@ -45,7 +58,7 @@ fn recovery_rates(trials: u64, sigma: f64) -> BTreeMap<&'static str, (f64, Vec<S
             for trial in 0..trials {
                 let data = synthetic::with_offset_share(model, trial, sigma, share);
                 attempted += 1;
-                match big_o::infer_complexity(&data) {
+                match analysis.infer(&data) {
                     Ok(inference) if inference.best.model == model => recovered += 1,
                     Ok(inference) => missed.push(format!(
                         "offset {share}, trial {trial}: {} ({:?})",
@@ -64,19 +77,48 @@ fn recovery_rates(trials: u64, sigma: f64) -> BTreeMap<&'static str, (f64, Vec<S
     rates
 }
 
-const MODELS: [Model; 8] = [
+const MODELS: [Model; 10] = [
+    Model::Constant,
+    Model::Logarithmic,
+    Model::CubeRoot,
+    Model::SquareRoot,
+    Model::Linear,
+    Model::Linearithmic,
+    Model::Quadratic,
+    Model::Cubic,
+    Model::Polynomial,
+    Model::Exponential,
+];
+
+/// Every model, for the sweep with the compound ones competing too.
+const POLYLOG_MODELS: [Model; 13] = [
     Model::Constant,
     Model::Logarithmic,
+    Model::LogSquared,
+    Model::CubeRoot,
+    Model::SquareRoot,
     Model::Linear,
     Model::Linearithmic,
+    Model::LinearLogSquared,
     Model::Quadratic,
+    Model::QuadraticLog,
     Model::Cubic,
     Model::Polynomial,
     Model::Exponential,
 ];
 
 fn assert_recovers(trials: u64, sigma: f64, required: f64) {
-    let rates = recovery_rates(trials, sigma);
+    assert_recovers_with(&MODELS, &Analysis::new(), trials, sigma, required);
+}
+
+fn assert_recovers_with(
+    models: &[Model],
+    analysis: &Analysis,
+    trials: u64,
+    sigma: f64,
+    required: f64,
+) {
+    let rates = recovery_rates(models, analysis, trials, sigma);
 
     let failures: Vec<String> = rates
         .iter()
@@ -88,9 +130,10 @@ fn assert_recovers(trials: u64, sigma: f64, required: f64) {
 
     assert!(
         failures.is_empty(),
-        "at {:.0}% noise, {} of 8 models fell below {:.0}% over {trials} trials per constant-term share:\n{}",
+        "at {:.0}% noise, {} of {} models fell below {:.0}% over {trials} trials per constant-term share:\n{}",
         sigma * 100.0,
         failures.len(),
+        models.len(),
         required * 100.0,
         failures.join("\n")
     );
@@ -106,12 +149,63 @@ fn recovers_every_model_through_realistic_noise() {
     assert_recovers(TRIALS, NOISE, REQUIRED);
 }
 
+#[test]
+fn recovers_every_model_with_the_compound_ones_competing() {
+    let analysis = Analysis::new().polylog();
+
+    assert_recovers_with(&POLYLOG_MODELS, &analysis, TRIALS, 0.0, 1.0);
+    assert_recovers_with(&POLYLOG_MODELS, &analysis, TRIALS, NOISE, POLYLOG_REQUIRED);
+}
+
+#[test]
+fn recovers_every_model_with_refinement() {
+    let analysis = Analysis::new().refine();
+
+    assert_recovers_with(&MODELS, &analysis, TRIALS, 0.0, 1.0);
+    assert_recovers_with(&MODELS, &analysis, TRIALS, NOISE, REQUIRED);
+}
+
+/// The information criteria and cross-validation recover every model from
+/// clean data. Through noise they are not held to [`REQUIRED`]: they have no
+/// reason to prefer a named model to a free exponent that fits as well, and
+/// take `O(n^2.01)` over `O(n^2)` about one time in four. Cross-validation,
+/// which counts no parameters at all, also reports a constant as a curve of
+/// negligible gain about one time in three. The tolerance rule exists for that.
+#[test]
+fn recovers_every_model_from_clean_data_by_every_criterion() {
+    for selection in [
+        Selection::Aic,
+        Selection::Aicc,
+        Selection::Bic,
+        // Leave-one-out refits six times as often as this, for the same verdicts.
+        Selection::CrossValidation(Validation::KFold(4)),
+    ] {
+        let analysis = Analysis::new().selection(selection);
+
+        assert_recovers_with(&MODELS, &analysis, TRIALS, 0.0, 1.0);
+    }
+}
+
 /// The wider sweep. Excluded from the default run for time; `--ignored` runs it.
 #[test]
 #[ignore = "wider sweep, run on a schedule rather than per commit"]
 fn recovers_every_model_over_many_trials() {
     assert_recovers(DEEP_TRIALS, 0.0, 1.0);
     assert_recovers(DEEP_TRIALS, NOISE, REQUIRED);
+
+    let analysis = Analysis::new().polylog();
+    assert_recovers_with(&POLYLOG_MODELS, &analysis, DEEP_TRIALS, 0.0, 1.0);
+    assert_recovers_with(
+        &POLYLOG_MODELS,
+        &analysis,
+        DEEP_TRIALS,
+        NOISE,
+        POLYLOG_REQUIRED,
+    );
+
+    let analysis = Analysis::new().refine();
+    assert_recovers_with(&MODELS, &analysis, DEEP_TRIALS, 0.0, 1.0);
+    assert_recovers_with(&MODELS, &analysis, DEEP_TRIALS, NOISE, REQUIRED);
 }
 
 /// Prints the full confusion matrix. Not an assertion — a way to see *how* a
@@ -119,14 +213,28 @@ fn recovers_every_model_over_many_trials() {
 #[test]
 #[ignore = "diagnostic, prints the confusion matrix"]
 fn report_confusion_matrix() {
-    for sigma in [0.0, NOISE] {
-        println!(
-            "\nnoise {:.0}%, {DEEP_TRIALS} trials per model per constant-term share {:?}",
-            sigma * 100.0,
-            synthetic::OFFSET_SHARES
-        );
-        for (model, (rate, missed)) in recovery_rates(DEEP_TRIALS, sigma) {
-            println!("  {model:<12} {:>5.1}%  {missed:?}", rate * 100.0);
+    let leave_one_out = Selection::CrossValidation(Validation::LeaveOneOut);
+    let sweeps: [(&str, &[Model], Analysis); 5] = [
+        ("default models", &MODELS, Analysis::new()),
+        ("with polylog", &POLYLOG_MODELS, Analysis::new().polylog()),
+        ("refined", &MODELS, Analysis::new().refine()),
+        ("by BIC", &MODELS, Analysis::new().selection(Selection::Bic)),
+        (
+            "by leave-one-out",
+            &MODELS,
+            Analysis::new().selection(leave_one_out),
+        ),
+    ];
+    for (label, models, analysis) in &sweeps {
+        for sigma in [0.0, NOISE] {
+            println!(
+                "\n{label}, noise {:.0}%, {DEEP_TRIALS} trials per model per constant-term share {:?}",
+                sigma * 100.0,
+                synthetic::OFFSET_SHARES
+            );
+            for (model, (rate, missed)) in recovery_rates(models, analysis, DEEP_TRIALS, sigma) {
+                println!("  {model:<12} {:>5.1}%  {missed:?}", rate * 100.0);
+            }
         }
     }
 }
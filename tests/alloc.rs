@@ -0,0 +1,109 @@
+//! The `alloc` feature: space complexity inferred from a counting allocator
+//! installed as this test binary's global one.
+//!
+//! The allocator counts every thread, and the test harness runs tests on
+//! several, so each test holds a lock while it measures.
+
+#![cfg(feature = "alloc")]
+
+use big_o::{CountingAllocator, Harness, Model};
+use std::alloc::System;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+
+static MEASURING: Mutex<()> = Mutex::new(());
+
+fn harness() -> Harness {
+    Harness::new().ladder(64, 65_536, 11).repeats(2)
+}
+
+#[test]
+fn a_copy_holds_linear_space_in_one_allocation() {
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+
+    let space = harness()
+        .infer_space(&ALLOCATOR, |n| vec![7u64; n], |input| input.clone())
+        .unwrap();
+
+    assert_eq!(space.peak_bytes.best.model, Model::Linear);
+    assert_eq!(space.total_bytes.best.model, Model::Linear);
+    assert_eq!(space.allocations.best.model, Model::Constant);
+}
+
+#[test]
+fn allocating_per_element_shows_in_the_count() {
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+
+    let space = harness()
+        .infer_space(
+            &ALLOCATOR,
+            |n| (0..n as u64).collect::<Vec<_>>(),
+            |input| input.iter().map(|&k| Box::new(k)).collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+    assert_eq!(space.allocations.best.model, Model::Linear);
+    assert_eq!(space.peak_bytes.best.model, Model::Linear);
+}
+
+#[test]
+fn memory_freed_along_the_way_counts_in_the_total_but_not_the_peak() {
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+
+    // One small buffer at a time, so the total grows with the input and the
+    // peak does not.
+    let space = harness()
+        .infer_space(
+            &ALLOCATOR,
+            |n| n,
+            |&n| (0..n).map(|k| vec![k as u8; 16].len()).sum::<usize>(),
+        )
+        .unwrap();
+
+    assert_eq!(space.total_bytes.best.model, Model::Linear);
+    assert!(space.peak_bytes.best.is_at_most(Model::Constant));
+}
+
+#[test]
+fn a_routine_that_never_allocates_is_constant_space() {
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+
+    let space = harness()
+        .infer_space(
+            &ALLOCATOR,
+            |n| vec![1u64; n],
+            |input| input.iter().sum::<u64>(),
+        )
+        .unwrap();
+
+    for inference in [space.peak_bytes, space.total_bytes, space.allocations] {
+        assert_eq!(inference.best.model, Model::Constant);
+        assert_eq!(inference.best.evaluate(1e6), 0.0);
+    }
+}
+
+#[test]
+fn a_growing_map_allocates_a_logarithmic_number_of_times() {
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+
+    // A map that doubles its table as it fills.
+    let space = harness()
+        .infer_space(
+            &ALLOCATOR,
+            |n| n as u64,
+            |&n| {
+                let mut map = HashMap::new();
+                for k in 0..n {
+                    map.insert(k, k);
+                }
+                map
+            },
+        )
+        .unwrap();
+
+    assert_eq!(space.allocations.best.model, Model::Logarithmic);
+    assert_eq!(space.peak_bytes.best.model, Model::Linear);
+}
@@ -2,7 +2,11 @@
 
 mod synthetic;
 
-use big_o::{Analysis, Error, Fit, Model, ModelParams, Warning};
+use big_o::{
+    assert_complexity, Aggregation, Analysis, Error, Fit, Harness, Loss, Measurement, Model,
+    ModelParams, MultiAnalysis, Selection, Shape, Validation, Warning,
+};
+use std::time::Duration;
 
 /// Clean measurements of a known curve, over a range wide enough to identify it.
 fn clean(model: Model) -> Vec<(f64, f64)> {
@@ -10,7 +14,7 @@ fn clean(model: Model) -> Vec<(f64, f64)> {
 }
 
 fn infer(model: Model) -> Fit {
-    big_o::infer_complexity(&clean(model))
+    big_o::infer_complexity(clean(model))
         .unwrap_or_else(|e| panic!("{model} data should be inferable: {e}"))
         .best
 }
@@ -20,6 +24,8 @@ fn recovers_every_model_from_its_own_curve() {
     for model in [
         Model::Constant,
         Model::Logarithmic,
+        Model::CubeRoot,
+        Model::SquareRoot,
         Model::Linear,
         Model::Linearithmic,
         Model::Quadratic,
@@ -31,11 +37,317 @@ fn recovers_every_model_from_its_own_curve() {
     }
 }
 
+#[test]
+fn recovers_the_compound_models_when_asked_to() {
+    for model in [
+        Model::LogSquared,
+        Model::LinearLogSquared,
+        Model::QuadraticLog,
+    ] {
+        let data = clean(model);
+
+        let polylog = Analysis::new().polylog().infer(&data).unwrap().best;
+        let default = big_o::infer_complexity(&data).unwrap().best;
+
+        assert_eq!(polylog.model, model);
+        assert_ne!(default.model, model, "compound models are opt-in");
+    }
+}
+
+#[test]
+fn orders_the_compound_models_by_their_logarithmic_factor() {
+    let n_log_squared = Analysis::new()
+        .polylog()
+        .infer(clean(Model::LinearLogSquared))
+        .unwrap()
+        .best;
+
+    assert!(n_log_squared.is_faster_than(Model::Quadratic));
+    assert!(!n_log_squared.is_at_most(Model::Linearithmic));
+    assert!(n_log_squared.is_at_most(Model::LinearLogSquared));
+}
+
+#[test]
+fn refinement_separates_a_setup_cost_from_the_exponent() {
+    let data: Vec<(f64, f64)> = clean(Model::Linear)
+        .into_iter()
+        .map(|(n, _)| (n, 4.0 * n.powf(1.4) + 2_000.0))
+        .collect();
+
+    let closed_form = Analysis::new().infer(&data).unwrap();
+    let refined = Analysis::new().refine().infer(&data).unwrap();
+
+    assert_ne!(closed_form.best.model, Model::Polynomial);
+    assert_eq!(refined.best.model, Model::Polynomial);
+    assert_eq!(refined.best.to_string(), "O(n^1.4)");
+    match refined.best.params {
+        ModelParams::Polynomial { offset, .. } => assert!((offset - 2_000.0).abs() < 1.0),
+        other => panic!("expected a power law, got {other:?}"),
+    }
+    assert!((refined.best.evaluate(1e4) - (4e4 * 1e4f64.powf(0.4) + 2_000.0)).abs() < 1.0);
+}
+
+#[test]
+fn an_information_criterion_ranks_and_weighs_every_fit() {
+    let data = synthetic::noisy(Model::Linearithmic, 3, 0.02);
+
+    for selection in [Selection::Aic, Selection::Aicc, Selection::Bic] {
+        let inference = Analysis::new().selection(selection).infer(&data).unwrap();
+
+        assert_eq!(inference.best.model, Model::Linearithmic, "{selection:?}");
+        assert_eq!(inference.criteria.len(), inference.all.len());
+        for (fit, criterion) in inference.all.iter().zip(&inference.criteria) {
+            assert_eq!(fit.model, criterion.model);
+        }
+        assert!(inference
+            .criteria
+            .windows(2)
+            .all(|pair| pair[0].value <= pair[1].value));
+        let total: f64 = inference.criteria.iter().map(|c| c.weight).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn the_default_selection_still_reports_criteria() {
+    let inference = big_o::infer_complexity(clean(Model::Quadratic)).unwrap();
+
+    assert_eq!(inference.criteria.len(), inference.all.len());
+    assert!(inference.criteria[0].weight > 0.5);
+}
+
+#[test]
+fn validation_reports_held_out_error_on_every_fit() {
+    let data = synthetic::noisy(Model::Linearithmic, 1, 0.03);
+
+    let plain = Analysis::new().infer(&data).unwrap();
+    let validated = Analysis::new()
+        .validation(Validation::LeaveOneOut)
+        .infer(&data)
+        .unwrap();
+
+    assert!(plain.all.iter().all(|fit| fit.validation_error.is_none()));
+    assert_eq!(
+        validated.best.model, plain.best.model,
+        "reporting is not ranking"
+    );
+    for fit in &validated.all {
+        let held_out = fit.validation_error.expect("every fit is validated");
+        assert!(held_out >= fit.relative_error, "{}", fit.model);
+    }
+}
+
+#[test]
+fn cross_validation_tells_n_log_n_from_a_nearby_power() {
+    let data = synthetic::noisy(Model::Linearithmic, 2, 0.02);
+
+    let inference = Analysis::new()
+        .selection(Selection::CrossValidation(Validation::KFold(5)))
+        .infer(&data)
+        .unwrap();
+
+    assert_eq!(inference.best.model, Model::Linearithmic);
+    let polynomial = inference
+        .all
+        .iter()
+        .find(|fit| fit.model == Model::Polynomial)
+        .unwrap();
+    assert!(polynomial.validation_error > inference.best.validation_error);
+}
+
+#[test]
+fn votes_account_for_every_counted_resample() {
+    let data = synthetic::noisy(Model::Linearithmic, 5, 0.08);
+
+    let inference = Analysis::new().resamples(400).infer(&data).unwrap();
+
+    let total: f64 = inference.votes.iter().map(|vote| vote.share).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!(inference
+        .votes
+        .windows(2)
+        .all(|pair| pair[0].share >= pair[1].share));
+    let best = inference
+        .votes
+        .iter()
+        .find(|vote| vote.model == inference.best.model)
+        .expect("the winner was voted for");
+    assert_eq!(best.share, inference.confidence);
+}
+
+#[test]
+fn the_seed_decides_the_draw_and_nothing_else_does() {
+    let data = synthetic::noisy(Model::Linear, 4, 0.1);
+    let infer = |analysis: Analysis| analysis.resamples(200).infer(&data).unwrap();
+
+    let a = infer(Analysis::new().seed(7));
+    let b = infer(Analysis::new().seed(7));
+    let c = infer(Analysis::new().seed(8));
+
+    assert_eq!(a.votes, b.votes);
+    assert_eq!(a.best, c.best, "the seed moves the estimate, not the fit");
+}
+
+#[test]
+fn no_resamples_is_no_confidence() {
+    let inference = Analysis::new()
+        .resamples(0)
+        .infer(clean(Model::Quadratic))
+        .unwrap();
+
+    assert_eq!(inference.best.model, Model::Quadratic);
+    assert_eq!(inference.confidence, 0.0);
+    assert!(inference.votes.is_empty());
+}
+
+#[test]
+fn the_interval_on_a_fitted_exponent_covers_the_true_one() {
+    let data: Vec<(f64, f64)> = (1..=24)
+        .map(|k| 10f64.powf(k as f64 / 6.0))
+        .enumerate()
+        .map(|(i, n)| (n, n.powf(1.3) * (1.0 + 0.1 * (i as f64 * 1.9).sin())))
+        .collect();
+
+    let inference = Analysis::new().uncertainty(0.95).infer(&data).unwrap();
+    let uncertainty = inference.best.uncertainty.expect("an interval was drawn");
+
+    match (
+        uncertainty.lower,
+        uncertainty.upper,
+        uncertainty.standard_error,
+    ) {
+        (
+            ModelParams::Polynomial { power: lower, .. },
+            ModelParams::Polynomial { power: upper, .. },
+            ModelParams::Polynomial { power: error, .. },
+        ) => {
+            assert!(lower < 1.3 && 1.3 < upper, "[{lower}, {upper}]");
+            assert!(error > 0.0 && error < upper - lower);
+        }
+        other => panic!("expected power law intervals, got {other:?}"),
+    }
+    assert!(format!("{:#}", inference.best).contains(" ± "));
+    assert!(!inference.best.to_string().contains('±'));
+}
+
+#[test]
+fn every_fit_gets_an_interval_but_only_when_asked() {
+    let data = synthetic::noisy(Model::Linear, 2, 0.05);
+
+    let plain = Analysis::new().infer(&data).unwrap();
+    let uncertain = Analysis::new().uncertainty(0.9).infer(&data).unwrap();
+
+    assert!(plain.all.iter().all(|fit| fit.uncertainty.is_none()));
+    assert!(uncertain.all.iter().all(|fit| fit.uncertainty.is_some()));
+    assert_eq!(uncertain.best.uncertainty.unwrap().level, 0.9);
+    assert_eq!(
+        format!("{:#}", uncertain.best),
+        uncertain.best.to_string(),
+        "a named model has no exponent to qualify"
+    );
+}
+
+#[test]
+fn a_prediction_ten_times_past_the_data_brackets_the_truth() {
+    let cost = |n: f64| 2.0 * n * n.ln();
+    let data: Vec<(f64, f64)> = (0..16)
+        .map(|k| 100.0 * 10f64.powf(k as f64 / 8.0))
+        .enumerate()
+        .map(|(i, n)| (n, cost(n) * (1.0 + 0.05 * (i as f64 * 2.3).sin())))
+        .collect();
+    let largest = data.last().unwrap().0;
+
+    let inference = big_o::infer_complexity(&data).unwrap();
+    let prediction = inference.predict(10.0 * largest);
+
+    let truth = cost(10.0 * largest);
+    assert!(
+        prediction.lower < truth && truth < prediction.upper,
+        "{truth} outside {prediction}"
+    );
+    assert!(prediction.lower <= prediction.value && prediction.value <= prediction.upper);
+    assert!((prediction.upper - prediction.lower) / truth < 0.5);
+}
+
+#[test]
+fn finds_where_one_implementation_overtakes_another_in_noisy_timings() {
+    let sizes = || (0..20).map(|k| 10f64.powf(1.0 + k as f64 / 8.0));
+    let jitter = |i: usize, seed: f64| 1.0 + 0.05 * (i as f64 * seed).sin();
+    let simple: Vec<(f64, f64)> = sizes()
+        .enumerate()
+        .map(|(i, n)| (n, 0.5 * n * n * jitter(i, 1.7)))
+        .collect();
+    let clever: Vec<(f64, f64)> = sizes()
+        .enumerate()
+        .map(|(i, n)| (n, 40.0 * n * jitter(i, 2.9)))
+        .collect();
+
+    let simple = big_o::infer_complexity(&simple).unwrap();
+    let clever = big_o::infer_complexity(&clever).unwrap();
+    let crossover = simple.crossover(&clever);
+
+    assert_eq!(simple.range, Some((10.0, sizes().next_back().unwrap())));
+    assert_eq!(crossover.crossings.len(), 1, "{crossover}");
+    let threshold = crossover.crossings[0];
+    assert!((threshold.at / 80.0 - 1.0).abs() < 0.15, "{threshold:?}");
+    assert!(!threshold.extrapolated);
+    assert_eq!(clever.crossover(&simple).crossings.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "strictly between 0 and 1")]
+fn an_interval_level_outside_zero_to_one_is_refused() {
+    let _ = Analysis::new().uncertainty(95.0);
+}
+
+/// `O(n log log n)`, as a caller outside the crate would define it.
+#[derive(Debug)]
+struct LogLog;
+
+impl Shape for LogLog {
+    fn notation(&self) -> &'static str {
+        "O(n log log n)"
+    }
+
+    fn degree(&self) -> f64 {
+        1.05
+    }
+
+    fn transform(&self, x: f64, cost: f64) -> Option<(f64, f64)> {
+        let u = x * x.ln().ln();
+        u.is_finite().then_some((u, cost))
+    }
+
+    fn inverse(&self, gain: f64, offset: f64, x: f64) -> f64 {
+        gain * x * x.ln().ln() + offset
+    }
+}
+
+#[test]
+fn a_caller_defined_shape_is_fitted_like_a_built_in_model() {
+    static LOG_LOG: LogLog = LogLog;
+    let data: Vec<(f64, f64)> = clean(Model::Linear)
+        .into_iter()
+        .filter(|&(x, _)| x > 2.0)
+        .map(|(x, _)| (x, 7.0 * LOG_LOG.inverse(1.0, 0.0, x) + 20.0))
+        .collect();
+
+    let inference = Analysis::new()
+        .models([Model::Linear, Model::Linearithmic, Model::Custom(&LOG_LOG)])
+        .infer(&data)
+        .unwrap();
+
+    assert_eq!(inference.best.model, Model::Custom(&LOG_LOG));
+    assert_eq!(inference.all.len(), 3);
+    assert!(inference.best.is_faster_than(Model::Linearithmic));
+    assert!(inference.confidence > 0.9);
+}
+
 #[test]
 fn recovers_the_coefficients_it_was_given() {
     let data = [(1., 5.), (2., 9.), (3., 13.), (4., 17.), (5., 21.)];
 
-    let fit = big_o::infer_complexity(&data).expect("linear data").best;
+    let fit = big_o::infer_complexity(data).expect("linear data").best;
 
     match fit.params {
         ModelParams::Linear { gain, offset } => {
@@ -51,7 +363,7 @@ fn recovers_the_coefficients_it_was_given() {
 
 #[test]
 fn ranks_every_model_that_could_be_fitted() {
-    let inference = big_o::infer_complexity(&clean(Model::Quadratic)).expect("quadratic data");
+    let inference = big_o::infer_complexity(clean(Model::Quadratic)).expect("quadratic data");
 
     assert!(inference.all.len() > 1, "all candidates should be reported");
     assert!(inference.all.contains(&inference.best));
@@ -82,7 +394,7 @@ fn narrowing_the_candidates_narrows_the_answer() {
 fn an_empty_candidate_set_infers_nothing() {
     let err = Analysis::new()
         .models([])
-        .infer(&clean(Model::Linear))
+        .infer(clean(Model::Linear))
         .unwrap_err();
 
     assert_eq!(err, Error::NoValidComplexity);
@@ -192,7 +504,7 @@ fn reports_low_confidence_when_the_data_barely_decides() {
         (1030., 1032.),
     ];
 
-    let inference = big_o::infer_complexity(&barely).expect("something fits");
+    let inference = big_o::infer_complexity(barely).expect("something fits");
 
     assert!(
         inference.confidence < 0.9,
@@ -205,7 +517,7 @@ fn reports_low_confidence_when_the_data_barely_decides() {
 fn warns_about_a_range_too_narrow_to_separate_the_models() {
     let narrow = [(1000., 1.), (1050., 2.), (1100., 3.), (1150., 4.)];
 
-    let inference = big_o::infer_complexity(&narrow).expect("something fits");
+    let inference = big_o::infer_complexity(narrow).expect("something fits");
 
     assert!(inference
         .warnings
@@ -230,7 +542,7 @@ fn warns_when_cost_rises_and_falls() {
 
 #[test]
 fn a_clean_wide_sample_warns_about_nothing() {
-    let inference = big_o::infer_complexity(&clean(Model::Quadratic)).expect("quadratic data");
+    let inference = big_o::infer_complexity(clean(Model::Quadratic)).expect("quadratic data");
 
     assert!(
         inference.warnings.is_empty(),
@@ -239,9 +551,252 @@ fn a_clean_wide_sample_warns_about_nothing() {
     );
 }
 
+#[test]
+fn a_cache_cliff_is_reported_and_split_into_regimes() {
+    // A linear scan whose cost per element triples once the data stops
+    // fitting in cache, with a few percent of timing noise.
+    let data: Vec<(f64, f64)> = (0..24)
+        .map(|k| 10f64.powf(1.0 + k as f64 / 6.0))
+        .enumerate()
+        .map(|(i, n)| {
+            let per_element = if n < 2_000.0 { 2.0 } else { 6.0 };
+            (n, per_element * n * (1.0 + 0.04 * (i as f64 * 1.3).sin()))
+        })
+        .collect();
+    let analysis = Analysis::new();
+
+    let whole = analysis.infer(&data).unwrap();
+    let breakpoints = whole
+        .warnings
+        .iter()
+        .find_map(|warning| match warning {
+            Warning::Piecewise { breakpoints } => Some(breakpoints.clone()),
+            _ => None,
+        })
+        .expect("the cliff is reported");
+    assert_eq!(breakpoints.len(), 1);
+    assert!((breakpoints[0] / 2_000.0).log10().abs() < 0.2);
+
+    let regimes = analysis.regimes(&data).unwrap();
+    assert_eq!(regimes.len(), 2);
+    for regime in &regimes {
+        assert_eq!(regime.inference.best.model, Model::Linear);
+    }
+    assert_eq!(regimes[0].from, data[0].0);
+    assert_eq!(regimes[1].to, data[23].0);
+}
+
+#[test]
+fn one_regime_comes_back_whole() {
+    let data = clean(Model::Linearithmic);
+
+    let regimes = Analysis::new().regimes(&data).unwrap();
+
+    assert_eq!(regimes.len(), 1);
+    assert_eq!(regimes[0].inference.best.model, Model::Linearithmic);
+}
+
+/// Linear, with the largest size measured once on a runner that was busy with
+/// something else for two thirds of the run.
+fn descheduled_ladder() -> Vec<(f64, f64)> {
+    let mut data: Vec<(f64, f64)> = (0..8)
+        .map(|k| 10f64.powf(2.0 + 3.0 * k as f64 / 7.0))
+        .enumerate()
+        .map(|(i, n)| (n, (3.0 * n + 50.0) * (1.0 + 0.02 * (i as f64 * 2.3).sin())))
+        .collect();
+    data[7].1 *= 3.0;
+    data
+}
+
+#[test]
+fn a_descheduled_run_flips_least_squares_but_not_a_robust_loss() {
+    let data = descheduled_ladder();
+
+    let fooled = Analysis::new().infer(&data).unwrap();
+    assert_eq!(fooled.best.model, Model::Linearithmic);
+    assert!(fooled.influence.is_empty());
+
+    for loss in [Loss::Huber, Loss::Bisquare] {
+        let robust = Analysis::new().loss(loss).infer(&data).unwrap();
+
+        assert_eq!(robust.best.model, Model::Linear, "{loss:?}");
+        let set_aside: Vec<f64> = robust
+            .influence
+            .iter()
+            .filter(|point| point.weight < 0.5)
+            .map(|point| point.size)
+            .collect();
+        assert_eq!(set_aside, [data[7].0], "{loss:?}");
+        assert!(robust.to_string().contains("set aside:  n=100000"));
+    }
+}
+
+#[test]
+fn the_tail_grows_faster_than_the_typical_run() {
+    // Nine runs in ten take the linear fast path; the tenth falls back to a
+    // quadratic one.
+    let data: Vec<(f64, f64)> = (0..12)
+        .map(|k| 10f64.powf(2.0 + k as f64 / 4.0))
+        .flat_map(|n| {
+            (0..10).map(move |i| match i {
+                9 => (n, n * n),
+                _ => (n, 50.0 * n * (1.0 + 0.01 * i as f64)),
+            })
+        })
+        .collect();
+
+    let typical = Analysis::new().infer(&data).unwrap();
+    let tail = Analysis::new()
+        .aggregation(Aggregation::Quantile(0.99))
+        .infer(&data)
+        .unwrap();
+
+    assert_eq!(typical.best.model, Model::Linear);
+    assert_eq!(tail.best.model, Model::Quadratic);
+}
+
+#[test]
+#[should_panic(expected = "a quantile is a probability between 0 and 1, got 99")]
+fn a_percentile_is_not_a_quantile() {
+    let _ = Analysis::new().aggregation(Aggregation::Quantile(99.0));
+}
+
+#[test]
+fn a_rejected_size_is_reported_and_no_longer_steers_the_fit() {
+    let data = descheduled_ladder();
+
+    let inference = Analysis::new().outliers(3.0).infer(&data).unwrap();
+
+    assert_eq!(inference.best.model, Model::Linear);
+    assert!(inference.warnings.contains(&Warning::OutliersRemoved {
+        count: 1,
+        sizes: vec![data[7].0],
+    }));
+    assert_eq!(inference.range, Some((data[0].0, data[6].0)));
+}
+
+#[test]
+fn clean_measurements_lose_nothing_to_the_outlier_filter() {
+    let data: Vec<(f64, f64)> = (0..8)
+        .map(|k| 10f64.powf(2.0 + 3.0 * k as f64 / 7.0))
+        .flat_map(|n| (0..5).map(move |i| (n, n * n.ln() * (1.0 + 0.01 * i as f64))))
+        .collect();
+
+    let filtered = Analysis::new().outliers(3.0).infer(&data).unwrap();
+
+    assert_eq!(filtered.best, Analysis::new().infer(&data).unwrap().best);
+    assert!(!filtered
+        .warnings
+        .iter()
+        .any(|w| matches!(w, Warning::OutliersRemoved { .. })));
+}
+
+#[test]
+#[should_panic(expected = "outliers are measured in a positive, finite number of deviations")]
+fn no_measurement_is_zero_deviations_out() {
+    let _ = Analysis::new().outliers(0.0);
+}
+
+#[test]
+fn enough_repeats_report_how_well_the_fit_matches_their_scatter() {
+    let runs = |repeats: usize| -> Vec<(f64, f64)> {
+        [100., 300., 1_000., 3_000., 10_000., 30_000.]
+            .iter()
+            .flat_map(|&n| (0..repeats).map(move |k| (n, k as f64)))
+            .map(|(n, k)| (n, 4.0 * n * (1.0 + 0.02 * (1.7 * k + n).sin())))
+            .collect()
+    };
+
+    let few = Analysis::new().infer(runs(5)).unwrap();
+    let many = Analysis::new().infer(runs(20)).unwrap();
+
+    assert_eq!(few.best.reduced_chi_squared, None);
+    assert_eq!(many.best.model, Model::Linear);
+    let chi_squared = many.best.reduced_chi_squared.unwrap();
+    assert!(chi_squared < 5.0, "{chi_squared}");
+    assert!(many.to_string().contains("chi^2/dof"));
+}
+
+#[test]
+fn infers_from_the_types_benchmarks_record() {
+    let floats: Vec<(f64, f64)> = (1..=16)
+        .map(|k| (k * 1_000) as f64)
+        .map(|n| (n, (n * n.ln()).round() * 7.0))
+        .collect();
+    let timings: Vec<(usize, Duration)> = floats
+        .iter()
+        .map(|&(n, cost)| (n as usize, Duration::from_nanos(cost as u64)))
+        .collect();
+    let counts: Vec<(u64, u64)> = floats
+        .iter()
+        .map(|&(n, cost)| (n as u64, cost as u64))
+        .collect();
+
+    let expected = big_o::infer_complexity(&floats).unwrap();
+    let from_timings = big_o::infer_complexity(&timings).unwrap();
+    let from_counts = Analysis::new().infer(counts).unwrap();
+
+    assert_eq!(expected.best.model, Model::Linearithmic);
+    for inference in [from_timings, from_counts] {
+        assert_eq!(inference.best, expected.best);
+        assert_eq!(inference.confidence, expected.confidence);
+        assert_eq!(inference.warnings, expected.warnings);
+    }
+}
+
+#[test]
+fn a_record_of_its_own_is_a_measurement() {
+    struct Run {
+        elements: usize,
+        cycles: u64,
+    }
+
+    impl Measurement for Run {
+        type Size = usize;
+        type Cost = u64;
+
+        fn size(&self) -> usize {
+            self.elements
+        }
+
+        fn cost(&self) -> u64 {
+            self.cycles
+        }
+    }
+
+    let runs: Vec<Run> = (1..=12)
+        .map(|k| 1usize << k)
+        .map(|n| Run {
+            elements: n,
+            cycles: (n * n) as u64,
+        })
+        .collect();
+
+    assert_eq!(
+        Analysis::new().infer(&runs).unwrap().best.model,
+        Model::Quadratic
+    );
+}
+
+#[test]
+fn counts_too_large_for_a_float_are_rounded_and_reported() {
+    // Cycle counts from a long run, odd and past 2^53, so no float holds them.
+    let data: Vec<(u64, u64)> = (1..=10)
+        .map(|n| (n, (1 << 54) * n + 1))
+        .chain([(11, 1 << 60)])
+        .collect();
+
+    let inference = Analysis::new().infer(&data).unwrap();
+
+    assert_eq!(inference.best.model, Model::Linear);
+    assert!(inference
+        .warnings
+        .contains(&Warning::LossyConversion { count: 10 }));
+}
+
 #[test]
 fn errors_read_as_sentences() {
-    let err = big_o::infer_complexity(&[(1.0, 5.0)]).unwrap_err();
+    let err = big_o::infer_complexity([(1.0, 5.0)]).unwrap_err();
 
     assert_eq!(
         err.to_string(),
@@ -249,3 +804,163 @@ fn errors_read_as_sentences() {
     );
     let _: &dyn std::error::Error = &err;
 }
+
+#[test]
+fn the_harness_infers_from_a_closure() {
+    // Timing noise cannot fake a thousandfold spread in cost, so a linear
+    // routine over three decades is at most quadratic however busy the machine.
+    let inference = Harness::new()
+        .ladder(1_000, 1_000_000, 7)
+        .repeats(3)
+        .infer(
+            |n| (0..n as u64).collect::<Vec<_>>(),
+            |input| input.iter().sum::<u64>(),
+        )
+        .expect("a linear routine is inferable");
+
+    assert!(inference.best.is_at_most(Model::Quadratic));
+    assert!(!inference.best.is_at_most(Model::Constant));
+}
+
+#[test]
+fn the_harness_infers_a_cost_other_than_time() {
+    let inference = Harness::new()
+        .infer_cost(|n| n, |&n| (n * n) as f64)
+        .expect("quadratic cost is inferable");
+
+    assert_eq!(inference.best.model, Model::Quadratic);
+    assert!(inference.warnings.is_empty(), "{:?}", inference.warnings);
+}
+
+#[test]
+fn the_harness_reports_every_timed_repeat() {
+    let measurements = Harness::new()
+        .sizes([10, 20, 40])
+        .repeats(2)
+        .measure(|n| vec![0u8; n], |input| input.len());
+
+    let sizes: Vec<f64> = measurements.iter().map(|&(x, _)| x).collect();
+    assert_eq!(sizes, vec![10., 10., 20., 20., 40., 40.]);
+    assert!(measurements.iter().all(|&(_, seconds)| seconds > 0.0));
+}
+
+#[test]
+fn asserts_a_complexity_bound() {
+    let data = clean(Model::Linearithmic);
+
+    assert_complexity!(data, at_most = Model::Quadratic);
+    assert_complexity!(data, faster_than = Model::Quadratic);
+    assert_complexity!(&data, exactly = Model::Linearithmic, min_confidence = 0.8);
+    assert_complexity!(
+        data[..],
+        at_most = Model::Linearithmic,
+        min_confidence = 0.8,
+    );
+}
+
+#[test]
+fn asserts_on_measurements_as_they_were_recorded() {
+    let timings: Vec<(usize, Duration)> = (1..=30)
+        .map(|k| 1usize << (k / 3 + 3))
+        .map(|n| (n, Duration::from_nanos(40 * n as u64 + 900)))
+        .collect();
+    let counts: [(u64, u64); 6] = [
+        (8, 64),
+        (16, 256),
+        (32, 1_024),
+        (64, 4_096),
+        (128, 16_384),
+        (256, 65_536),
+    ];
+
+    assert_complexity!(&timings, exactly = Model::Linear);
+    assert_complexity!(timings[..], at_most = Model::Linear);
+    assert_complexity!(counts, exactly = Model::Quadratic);
+}
+
+#[test]
+#[should_panic(expected = "expected exactly O(n), inferred O(n log n)")]
+fn a_failed_complexity_assertion_says_what_was_inferred_instead() {
+    assert_complexity!(clean(Model::Linearithmic), exactly = Model::Linear);
+}
+
+#[test]
+fn infers_a_complexity_in_two_sizes() {
+    let mut data = Vec::new();
+    for n in [8., 64., 512., 4096.] {
+        for m in [10., 100., 1000., 10000.] {
+            // Three repeats, one of them descheduled.
+            data.push(([n, m], n * m));
+            data.push(([n, m], n * m * 1.01));
+            data.push(([n, m], n * m * 5.0));
+        }
+    }
+
+    let inference = MultiAnalysis::new(["n", "m"]).infer(&data).unwrap();
+
+    assert_eq!(inference.best.to_string(), "O(n*m)");
+    assert!(inference.confidence > 0.9);
+    assert!(inference.to_string().starts_with("best:       O(n*m)"));
+    assert!((inference.best.evaluate(&[10., 10.]) - 101.0).abs() < 1.0);
+}
+
+/// `n` keys in no particular order, the same on every run.
+fn scrambled(n: usize) -> Vec<u64> {
+    (0..n as u64)
+        .map(|k| k.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect()
+}
+
+#[test]
+fn counted_comparisons_of_a_sort_are_n_log_n_with_certainty() {
+    let inference = Harness::new()
+        .repeats(1)
+        .infer_operations(scrambled, |input, counter| {
+            let mut sorted = input.clone();
+            sorted.sort_unstable_by(|a, b| {
+                counter.increment();
+                a.cmp(b)
+            });
+            sorted
+        })
+        .expect("comparison counts are inferable");
+
+    assert_eq!(inference.best.model, Model::Linearithmic);
+    assert!(inference.confidence > 0.95, "{inference}");
+}
+
+#[test]
+fn counted_operations_tell_the_quadratic_sort_from_the_logarithmic_search() {
+    let harness = Harness::new().ladder(16, 4_096, 9).repeats(1);
+
+    let insertion = harness
+        .infer_operations(scrambled, |input, counter| {
+            let mut sorted = input.clone();
+            for i in 1..sorted.len() {
+                let mut j = i;
+                while j > 0 && {
+                    counter.increment();
+                    sorted[j - 1] > sorted[j]
+                } {
+                    sorted.swap(j - 1, j);
+                    j -= 1;
+                }
+            }
+            sorted
+        })
+        .unwrap();
+    let search = harness
+        .infer_operations(
+            |n| (0..n as u64).collect::<Vec<_>>(),
+            |input, counter| {
+                input.binary_search_by(|probe| {
+                    counter.increment();
+                    probe.cmp(&u64::MAX)
+                })
+            },
+        )
+        .unwrap();
+
+    assert_eq!(insertion.best.model, Model::Quadratic);
+    assert_eq!(search.best.model, Model::Logarithmic);
+}
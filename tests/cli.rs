@@ -0,0 +1,87 @@
+//! The `cli` feature: the `big_o` binary, run the way a shell script would.
+
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary with `args`, feeding `input` on standard input.
+fn big_o(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_big_o"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary runs");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .expect("the binary reads its input");
+    child.wait_with_output().expect("the binary exits")
+}
+
+fn quadratic_csv() -> String {
+    let mut csv = String::from("size,nanoseconds\n");
+    for k in 0..16 {
+        let n = 2f64.powi(k);
+        csv.push_str(&format!("{n},{}\n", 3.0 * n * n + 50.0));
+    }
+    csv
+}
+
+#[test]
+fn infers_from_csv_on_standard_input() {
+    let output = big_o(&[], &quadratic_csv());
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("best:       O(n^2)"), "{stdout}");
+    assert!(stdout.contains("confidence:"), "{stdout}");
+}
+
+#[test]
+fn writes_the_inference_as_json() {
+    let output = big_o(&["--output", "json"], &quadratic_csv());
+
+    assert!(output.status.success());
+    let inference: big_o::Inference =
+        serde_json::from_slice(&output.stdout).expect("the output is an inference");
+    assert_eq!(inference.best.model, big_o::Model::Quadratic);
+}
+
+#[test]
+fn restricts_the_models_and_accepts_a_short_range() {
+    let tsv = "1\t1\n2\t4\n3\t9\n4\t16\n";
+
+    let output = big_o(&["-f", "tsv", "-m", "linear,cubic", "-a", "4,0.5"], tsv);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("O(n^2)"), "{stdout}");
+    assert!(stdout.contains("warnings:   none"), "{stdout}");
+}
+
+#[test]
+fn reads_json_input() {
+    let output = big_o(&[], "[[1, 3], [10, 30], [100, 300], [1000, 3000]]");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("best:       O(n)"));
+}
+
+#[test]
+fn reports_bad_input_and_failed_inference_by_exit_status() {
+    let bad = big_o(&[], "1,2\nthree,four\n");
+    assert_eq!(bad.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("line 2"));
+
+    let thin = big_o(&[], "1,2\n");
+    assert_eq!(thin.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&thin.stderr).contains("Need at least 3"));
+
+    let usage = big_o(&["--frobnicate"], "");
+    assert_eq!(usage.status.code(), Some(2));
+}
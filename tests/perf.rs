@@ -0,0 +1,84 @@
+//! The `perf` feature: complexity inferred from what the kernel counts.
+//!
+//! A sandbox or container may refuse every counter, so each test that needs
+//! one skips without it, saying so on stderr — unless `PERF_REQUIRED` is set,
+//! as it should be on a runner known to have counters, where a refusal fails
+//! the test instead. Where only the task clock is available, the counts are as
+//! noisy as a timing, and the assertions loosen to match.
+
+#![cfg(all(feature = "perf", target_os = "linux"))]
+
+use big_o::{Error, Event, Harness, Model, PerfCounter};
+
+/// A counter, or `None` to skip the test where the kernel refuses one.
+fn counter() -> Option<PerfCounter> {
+    match PerfCounter::new() {
+        Ok(counter) => Some(counter),
+        Err(err) if std::env::var_os("PERF_REQUIRED").is_some() => {
+            panic!("PERF_REQUIRED is set, but {err}")
+        }
+        Err(err) => {
+            eprintln!("skipped: {err}");
+            None
+        }
+    }
+}
+
+fn harness() -> Harness {
+    Harness::new().ladder(1_000, 1_000_000, 7).repeats(5)
+}
+
+#[test]
+fn a_sum_counts_linearly() {
+    let Some(counter) = counter() else {
+        return;
+    };
+
+    let inference = harness()
+        .infer_counted(
+            &counter,
+            |n| (0..n as u64).collect::<Vec<_>>(),
+            |input| input.iter().sum::<u64>(),
+        )
+        .unwrap();
+
+    match counter.event() {
+        Event::Instructions => assert_eq!(inference.best.model, Model::Linear),
+        _ => assert!(inference.best.is_at_most(Model::Linearithmic)),
+    }
+}
+
+#[test]
+fn comparing_every_pair_counts_quadratically() {
+    let Some(counter) = counter() else {
+        return;
+    };
+
+    let inference = Harness::new()
+        .ladder(100, 3_000, 7)
+        .repeats(3)
+        .infer_counted(
+            &counter,
+            |n| (0..n as u32).collect::<Vec<_>>(),
+            |input| {
+                let mut equal = 0u32;
+                for a in input {
+                    for b in input {
+                        equal += u32::from(std::hint::black_box(a == b));
+                    }
+                }
+                equal
+            },
+        )
+        .unwrap();
+
+    assert!(!inference.best.is_at_most(Model::Linearithmic));
+}
+
+#[test]
+fn an_unavailable_counter_says_why() {
+    let message = Error::CounterUnavailable { errno: 2 }.to_string();
+
+    assert!(message.starts_with("No performance counter could be opened: "));
+    assert!(message.contains("os error 2"));
+}
@@ -124,12 +124,24 @@ proptest! {
 fn coefficients_are_finite(params: &ModelParams) -> bool {
     match *params {
         ModelParams::Constant { offset } => offset.is_finite(),
-        ModelParams::Polynomial { gain, power } => gain.is_finite() && power.is_finite(),
-        ModelParams::Exponential { gain, base } => gain.is_finite() && base.is_finite(),
+        ModelParams::Polynomial {
+            gain,
+            power,
+            offset,
+        } => gain.is_finite() && power.is_finite() && offset.is_finite(),
+        ModelParams::Exponential { gain, base, offset } => {
+            gain.is_finite() && base.is_finite() && offset.is_finite()
+        }
         ModelParams::Logarithmic { gain, offset }
+        | ModelParams::LogSquared { gain, offset }
+        | ModelParams::CubeRoot { gain, offset }
+        | ModelParams::SquareRoot { gain, offset }
         | ModelParams::Linear { gain, offset }
         | ModelParams::Linearithmic { gain, offset }
+        | ModelParams::LinearLogSquared { gain, offset }
         | ModelParams::Quadratic { gain, offset }
-        | ModelParams::Cubic { gain, offset } => gain.is_finite() && offset.is_finite(),
+        | ModelParams::QuadraticLog { gain, offset }
+        | ModelParams::Cubic { gain, offset }
+        | ModelParams::Custom { gain, offset, .. } => gain.is_finite() && offset.is_finite(),
     }
 }
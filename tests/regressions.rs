@@ -57,7 +57,7 @@ fn noisy_quadratic_data_reports_a_named_complexity() {
 /// order the models happened to be tried in.
 #[test]
 fn a_single_measurement_is_not_enough_to_infer_from() {
-    let err = big_o::infer_complexity(&[(1.0, 5.0)]).unwrap_err();
+    let err = big_o::infer_complexity([(1.0, 5.0)]).unwrap_err();
 
     assert_eq!(err, Error::NotEnoughData { needed: 3, got: 1 });
 }
@@ -120,7 +120,7 @@ fn a_non_finite_measurement_never_becomes_a_fit() {
     assert!(inference.best.relative_error.is_finite());
 
     // With nothing finite left there is nothing to infer from.
-    let err = big_o::infer_complexity(&[(1.0, f64::NAN), (2.0, f64::NAN)]).unwrap_err();
+    let err = big_o::infer_complexity([(1.0, f64::NAN), (2.0, f64::NAN)]).unwrap_err();
     assert_eq!(err, Error::NotEnoughData { needed: 3, got: 0 });
 }
 
@@ -141,3 +141,51 @@ fn regression_0001() {
         inference.all
     );
 }
+
+/// Rescaling the costs of three degenerate points changed the answer between
+/// `O(n^(1/3))` and `O(sqrt n)`.
+///
+/// Both fit exactly, to errors of `1e-138` and `1e-16` before the rescaling and
+/// an identical `9e-17` after it, and the tie was broken by that rounding
+/// noise. Exact fits are now told apart by degree alone.
+#[test]
+fn exact_fits_are_not_ranked_by_rounding_noise() {
+    let data = [
+        (0.0, 0.0),
+        (6.781855991659642e299, 926233312123.6141),
+        (443473889877.1534, 0.0),
+    ];
+    let scaled: Vec<(f64, f64)> = data.iter().map(|&(x, y)| (x, y * 14083.85)).collect();
+
+    let plain = big_o::infer_complexity(data).unwrap().best.model;
+    let rescaled = big_o::infer_complexity(&scaled).unwrap().best.model;
+
+    assert_eq!(plain, rescaled);
+}
+
+/// A size whose ten repeats all cost exactly nothing, beside sizes that
+/// scattered, was given a variance of zero and so an infinite weight, and the
+/// reduced chi-squared divided by it.
+///
+/// That is what an allocation or operation count looks like at a size too
+/// small to do any work. The variance is now held to a share of the sample's
+/// typical cost, so the point weighs heavily but finitely.
+#[test]
+fn a_size_that_costs_exactly_nothing_every_time_keeps_a_finite_weight() {
+    let mut data = vec![(1.0, 0.0); 10];
+    for n in [10.0, 100.0, 1_000.0, 10_000.0, 100_000.0] {
+        for k in 0..10 {
+            let noise = 1.0 + 0.05 * (k as f64 * 1.7).sin();
+            data.push((n, 3.0 * n * noise));
+        }
+    }
+
+    let inference = big_o::infer_complexity(&data).expect("linear costs are inferable");
+
+    assert_eq!(inference.best.model, Model::Linear);
+    for fit in &inference.all {
+        if let Some(chi_squared) = fit.reduced_chi_squared {
+            assert!(chi_squared.is_finite(), "{fit:?}");
+        }
+    }
+}
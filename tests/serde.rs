@@ -16,7 +16,7 @@ fn quadratic() -> big_o::Inference {
         (1600., 2_570_240.),
         (3200., 10_352_640.),
     ];
-    big_o::infer_complexity(&data).expect("quadratic data infers")
+    big_o::infer_complexity(data).expect("quadratic data infers")
 }
 
 #[test]
@@ -27,10 +27,72 @@ fn inference_round_trips() {
 
     assert_eq!(after.best, before.best);
     assert_eq!(after.all, before.all);
+    assert_eq!(after.criteria, before.criteria);
+    assert_eq!(after.votes, before.votes);
+    assert_eq!(after.range, before.range);
     assert_eq!(after.warnings, before.warnings);
     assert!((after.confidence - before.confidence).abs() < f64::EPSILON);
 }
 
+#[test]
+fn uncertainty_round_trips() {
+    let data: Vec<(f64, f64)> = (1..=16)
+        .map(|k| 10f64.powf(k as f64 / 4.0))
+        .enumerate()
+        .map(|(i, n)| (n, n.powf(1.5) * (1.0 + 0.1 * (i as f64).sin())))
+        .collect();
+    let before = big_o::Analysis::new()
+        .uncertainty(0.9)
+        .infer(&data)
+        .expect("the power law infers");
+
+    let json = serde_json::to_string(&before.best).expect("fit serializes");
+    let after: big_o::Fit = serde_json::from_str(&json).expect("fit deserializes");
+
+    assert!(after.uncertainty.is_some());
+    assert_eq!(after, before.best);
+}
+
+#[test]
+fn influence_round_trips() {
+    let mut data: Vec<(f64, f64)> = (1..=12)
+        .map(|n| (n as f64, 3.0 * n as f64 + 10.0))
+        .collect();
+    data[8].1 *= 3.0;
+    let before = big_o::Analysis::new()
+        .loss(big_o::Loss::Bisquare)
+        .infer(&data)
+        .expect("the line infers");
+
+    let json = serde_json::to_string(&before).expect("inference serializes");
+    let after: big_o::Inference = serde_json::from_str(&json).expect("inference deserializes");
+
+    assert_eq!(after.influence, before.influence);
+    assert_eq!(after.influence.len(), 12);
+    assert_eq!(
+        serde_json::to_string(&big_o::Loss::Huber).expect("loss serializes"),
+        "\"Huber\""
+    );
+}
+
+#[test]
+fn reduced_chi_squared_round_trips() {
+    let data: Vec<(f64, f64)> = (1..=8)
+        .flat_map(|n| (0..12).map(move |k| (n as f64, k as f64)))
+        .map(|(n, k)| (n, 3.0 * n * (1.0 + 0.01 * (k + n).sin())))
+        .collect();
+    let before = big_o::infer_complexity(&data).expect("the line infers");
+
+    let json = serde_json::to_string(&before).expect("inference serializes");
+    let after: big_o::Inference = serde_json::from_str(&json).expect("inference deserializes");
+
+    assert!(before.best.reduced_chi_squared.is_some());
+    assert_eq!(
+        after.best.reduced_chi_squared,
+        before.best.reduced_chi_squared
+    );
+}
+
 #[test]
 fn model_name_is_stable_in_json() {
     let json = serde_json::to_string(&big_o::Model::Linearithmic).expect("model serializes");
@@ -38,3 +100,94 @@ fn model_name_is_stable_in_json() {
     // baselines, so it is pinned here.
     assert_eq!(json, "\"Linearithmic\"");
 }
+
+#[test]
+fn verdict_round_trips() {
+    let linear = big_o::infer_complexity([(1., 1.), (10., 10.), (100., 100.), (1000., 1000.)])
+        .expect("linear data infers");
+    let verdict = quadratic().compare(&linear);
+
+    let json = serde_json::to_string(&verdict).expect("verdict serializes");
+    let after: big_o::Verdict = serde_json::from_str(&json).expect("verdict deserializes");
+
+    assert_eq!(after, verdict);
+    assert!(after.is_regression());
+}
+
+#[test]
+fn crossover_round_trips() {
+    let linear = big_o::infer_complexity([(1., 100.), (10., 1000.), (100., 10_000.)])
+        .expect("linear data infers");
+    let crossover = quadratic().crossover(&linear);
+
+    let json = serde_json::to_string(&crossover).expect("crossover serializes");
+    let after: big_o::Crossover = serde_json::from_str(&json).expect("crossover deserializes");
+
+    assert_eq!(after, crossover);
+    assert!(!after.crossings.is_empty());
+}
+
+#[test]
+fn multi_inference_round_trips() {
+    let data: Vec<([f64; 2], f64)> = [1., 10., 100., 1000.]
+        .iter()
+        .flat_map(|&v| [2., 20., 200., 2000.].map(|e| ([v, e], v + e)))
+        .collect();
+    let before = big_o::MultiAnalysis::new(["V", "E"])
+        .infer(&data)
+        .expect("a grid infers");
+
+    let json = serde_json::to_string(&before).expect("inference serializes");
+    let after: big_o::MultiInference = serde_json::from_str(&json).expect("inference deserializes");
+
+    assert_eq!(after.best, before.best);
+    assert_eq!(after.best.to_string(), "O(V + E)");
+    assert_eq!(after.warnings, before.warnings);
+}
+
+#[derive(Debug)]
+struct Halved;
+
+impl big_o::Shape for Halved {
+    fn notation(&self) -> &'static str {
+        "O(n/2)"
+    }
+
+    fn degree(&self) -> f64 {
+        1.0
+    }
+
+    fn transform(&self, x: f64, cost: f64) -> Option<(f64, f64)> {
+        Some((x / 2.0, cost))
+    }
+
+    fn inverse(&self, gain: f64, offset: f64, x: f64) -> f64 {
+        gain * x / 2.0 + offset
+    }
+}
+
+#[test]
+fn a_custom_model_is_written_as_its_notation_and_not_read_back() {
+    let model = big_o::Model::Custom(&Halved);
+
+    let json = serde_json::to_string(&model).expect("custom model serializes");
+
+    assert_eq!(json, r#"{"Custom":"O(n/2)"}"#);
+    assert!(serde_json::from_str::<big_o::Model>(&json).is_err());
+}
+
+#[test]
+fn a_power_law_stored_before_offsets_existed_reads_with_none() {
+    let json = r#"{"Polynomial":{"gain":2.0,"power":1.5}}"#;
+
+    let params: big_o::ModelParams = serde_json::from_str(json).expect("old params deserialize");
+
+    assert_eq!(
+        params,
+        big_o::ModelParams::Polynomial {
+            gain: 2.0,
+            power: 1.5,
+            offset: 0.0,
+        }
+    );
+}
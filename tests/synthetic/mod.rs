@@ -36,6 +36,14 @@ pub const DECADES: f64 = 4.0;
 /// those measures signal-to-noise rather than the thing under test.
 pub const OFFSET_SHARES: [f64; 2] = [0.0, 0.2];
 
+/// The exponents `Model::CubeRoot` and `Model::SquareRoot` name.
+const ROOTS: [f64; 2] = [1.0 / 3.0, 0.5];
+
+/// How close to a root a free exponent may be drawn. Nearer than this, a
+/// curve over four decades differs from the root by less than the noise, and
+/// naming the root is as right an answer as the exponent.
+const ROOT_BAND: f64 = 0.05;
+
 /// Multiplicative timing noise: `y * (1 + e)`, `e ~ N(0, sigma)`.
 ///
 /// Benchmark noise scales with the measurement — a run that is 3% slow is 3%
@@ -93,6 +101,18 @@ fn curve(model: Model, rng: &mut Rng, offset_share: f64) -> Box<dyn Fn(f64) -> f
             let offset = offset_of(largest.ln());
             Box::new(move |x| gain * x.ln() + offset)
         }
+        Model::LogSquared => {
+            let offset = offset_of(largest.ln().powi(2));
+            Box::new(move |x| gain * x.ln().powi(2) + offset)
+        }
+        Model::CubeRoot => {
+            let offset = offset_of(largest.cbrt());
+            Box::new(move |x| gain * x.cbrt() + offset)
+        }
+        Model::SquareRoot => {
+            let offset = offset_of(largest.sqrt());
+            Box::new(move |x| gain * x.sqrt() + offset)
+        }
         Model::Linear => {
             let offset = offset_of(largest);
             Box::new(move |x| gain * x + offset)
@@ -101,18 +121,32 @@ fn curve(model: Model, rng: &mut Rng, offset_share: f64) -> Box<dyn Fn(f64) -> f
             let offset = offset_of(largest * largest.ln());
             Box::new(move |x| gain * x * x.ln() + offset)
         }
+        Model::LinearLogSquared => {
+            let offset = offset_of(largest * largest.ln().powi(2));
+            Box::new(move |x| gain * x * x.ln().powi(2) + offset)
+        }
         Model::Quadratic => {
             let offset = offset_of(largest.powi(2));
             Box::new(move |x| gain * x.powi(2) + offset)
         }
+        Model::QuadraticLog => {
+            let offset = offset_of(largest.powi(2) * largest.ln());
+            Box::new(move |x| gain * x.powi(2) * x.ln() + offset)
+        }
         Model::Cubic => {
             let offset = offset_of(largest.powi(3));
             Box::new(move |x| gain * x.powi(3) + offset)
         }
         Model::Polynomial => {
             // Kept clear of the whole numbers, where the honest answer is the
-            // named model rather than the free exponent.
-            let power = rng.pick(&[(0.4, 0.8), (1.3, 1.7), (2.3, 2.7), (3.3, 3.7)]);
+            // named model rather than the free exponent, and redrawn near the
+            // roots for the same reason.
+            let power = loop {
+                let power = rng.pick(&[(0.4, 0.8), (1.3, 1.7), (2.3, 2.7), (3.3, 3.7)]);
+                if ROOTS.iter().all(|root| (power - root).abs() > ROOT_BAND) {
+                    break power;
+                }
+            };
             Box::new(move |x| gain * x.powf(power))
         }
         Model::Exponential => {
@@ -120,6 +154,10 @@ fn curve(model: Model, rng: &mut Rng, offset_share: f64) -> Box<dyn Fn(f64) -> f
             let gain = gain / 100.0;
             Box::new(move |x| gain * base.powf(x))
         }
+        Model::Custom(shape) => {
+            let offset = offset_of(shape.inverse(1.0, 0.0, largest));
+            Box::new(move |x| shape.inverse(gain, offset, x))
+        }
     }
 }
 